@@ -0,0 +1,93 @@
+//! Compares rebuilding all batches with the evaluation by [`Evaluator`] and its batchings
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+use order_batching::batching::Evaluator;
+use order_batching::ga::orders::BatchedArticles;
+use order_batching::input::{Article, ArticleLocation, Input, Order};
+use order_batching::{CostModel, Model};
+
+const NUM_ARTICLES: u16 = 2_000;
+const NUM_ORDERS: u16 = 4_000;
+const ARTICLES_PER_ORDER: u16 = 5;
+
+/// An input with 20k ordered articles in two warehouses
+fn input() -> Input {
+    Input {
+        article_locations: (0..NUM_ARTICLES)
+            .map(|article_id| ArticleLocation {
+                warehouse: article_id % 2,
+                aisle: article_id / 2 % 20,
+                position: article_id / 40 % 20,
+                article_id,
+            })
+            .collect(),
+        orders: (0..NUM_ORDERS)
+            .map(|order_id| Order {
+                order_id,
+                article_ids: (0..ARTICLES_PER_ORDER)
+                    .map(|idx| (order_id * 7 + idx * 401) % NUM_ARTICLES)
+                    .collect(),
+            })
+            .collect(),
+        articles: (0..NUM_ARTICLES)
+            .map(|article_id| Article {
+                article_id,
+                volume: 1 + article_id % 10,
+            })
+            .collect(),
+    }
+}
+
+/// Fills batches of about 100 articles in the order of the ordered articles
+fn batch_mapping(model: &Model) -> Vec<usize> {
+    (0..model.max_batches_num()).map(|idx| idx / 100).collect()
+}
+
+fn evaluation(c: &mut Criterion) {
+    let model = Model::from_input(&input(), CostModel::default()).unwrap();
+    let evaluator = Evaluator::new(&model);
+    let cost_model = model.cost_model();
+    let batch_mapping = batch_mapping(&model);
+
+    let mut group = c.benchmark_group("evaluate batch mapping");
+    group.sample_size(20);
+    group.bench_function("rebuild", |b| {
+        b.iter(|| {
+            BatchedArticles::from_batch_mapping(black_box(batch_mapping.clone()), &model)
+                .cost(cost_model)
+        })
+    });
+    group.bench_function("evaluator", |b| {
+        b.iter(|| evaluator.cost(black_box(&batch_mapping)))
+    });
+    group.bench_function("counters", |b| {
+        b.iter(|| evaluator.batching(black_box(batch_mapping.clone())).cost())
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("evaluate single move");
+    group.sample_size(20);
+    group.bench_function("rebuild", |b| {
+        b.iter_batched(
+            || batch_mapping.clone(),
+            |mut batch_mapping| {
+                batch_mapping[black_box(0)] = 1;
+                BatchedArticles::from_batch_mapping(batch_mapping, &model).cost(cost_model)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("counters", |b| {
+        let mut batching = evaluator.batching(batch_mapping.clone());
+        b.iter(|| {
+            batching.move_article(black_box(0), 1);
+            let cost = batching.cost();
+            batching.move_article(black_box(0), 0);
+            cost
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, evaluation);
+criterion_main!(benches);
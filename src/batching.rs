@@ -0,0 +1,431 @@
+//! Evaluation of batch mappings, with batchings whose cost is kept up to date while articles move
+//! between batches
+use std::collections::BTreeMap;
+
+use crate::cost::CostModel;
+use crate::ga::orders::{BatchId, BatchMapping};
+use crate::model::*;
+
+/// The ordered articles of a model, prepared for evaluating many batch mappings
+///
+/// Evaluates a batch mapping in a single pass over the articles, instead of building all batches
+/// like [`crate::ga::orders::BatchedArticles`].
+#[derive(Debug, Clone)]
+pub struct Evaluator<'a> {
+    cost_model: &'a CostModel,
+    /// Ordered articles of the model, indexed like the batch mappings
+    articles: Vec<&'a OrderedArticle>,
+    /// Indices of the articles, sorted by warehouse, aisle and position
+    by_location: Vec<usize>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(model: &'a Model) -> Evaluator<'a> {
+        let articles = model.get_ordered_articles();
+        let mut by_location = (0..articles.len()).collect::<Vec<_>>();
+        by_location.sort_by_key(|idx| location_key(&articles[*idx].location));
+
+        Evaluator {
+            cost_model: model.cost_model(),
+            articles,
+            by_location,
+        }
+    }
+
+    pub fn cost_model(&self) -> &'a CostModel {
+        self.cost_model
+    }
+
+    pub fn num_articles(&self) -> usize {
+        self.articles.len()
+    }
+
+    pub fn article(&self, idx: usize) -> &'a OrderedArticle {
+        self.articles[idx]
+    }
+
+    /// Tour cost plus rest cost of all batches, `None` if a batch exceeds the max weight
+    pub fn cost(&self, batch_mapping: &BatchMapping) -> Option<usize> {
+        match self.overweight(batch_mapping) {
+            0 => Some(self.unchecked_cost(batch_mapping)),
+            _ => None,
+        }
+    }
+
+    /// Tour cost plus rest cost of all batches regardless of their weight, and the total volume by
+    /// which the batches exceed the max weight
+    pub fn cost_and_overweight(&self, batch_mapping: &BatchMapping) -> (usize, Volume) {
+        (
+            self.unchecked_cost(batch_mapping),
+            self.overweight(batch_mapping),
+        )
+    }
+
+    fn overweight(&self, batch_mapping: &BatchMapping) -> Volume {
+        // there are at most as many batches as articles
+        let mut volumes = vec![0; self.articles.len()];
+        for (batch_id, article) in batch_mapping.iter().zip(self.articles.iter()) {
+            volumes[*batch_id] += article.volume;
+        }
+
+        volumes
+            .iter()
+            .map(|volume| volume.saturating_sub(self.cost_model.max_weight_per_batch))
+            .sum()
+    }
+
+    fn unchecked_cost(&self, batch_mapping: &BatchMapping) -> usize {
+        // visited aisles of every batch with their deepest position, in the order of the aisles
+        let mut depths: Vec<Vec<((ID, ID), ID)>> = vec![Vec::new(); self.articles.len()];
+        for idx in self.by_location.iter() {
+            let location = self.articles[*idx].location;
+            let aisle = (location.warehouse, location.aisle);
+            let batch_depths = &mut depths[batch_mapping[*idx]];
+            match batch_depths.last_mut() {
+                Some((last_aisle, depth)) if *last_aisle == aisle => *depth = location.position,
+                _ => batch_depths.push((aisle, location.position)),
+            }
+        }
+
+        depths
+            .into_iter()
+            .filter(|batch_depths| !batch_depths.is_empty())
+            // every batch pays its cost twice: as part of its tour and as rest cost
+            .map(|batch_depths| {
+                self.cost_model.aisles_cost(batch_depths) + 2 * self.cost_model.cost_per_batch
+            })
+            .sum()
+    }
+
+    /// Number of orders whose articles are in more than one batch
+    pub fn num_split_orders(&self, batch_mapping: &BatchMapping) -> usize {
+        // the articles of every order are next to each other
+        let indices = (0..self.articles.len()).collect::<Vec<_>>();
+        indices
+            .chunk_by(|a, b| self.articles[*a].order_id == self.articles[*b].order_id)
+            .filter(|order| {
+                order
+                    .iter()
+                    .any(|idx| batch_mapping[*idx] != batch_mapping[order[0]])
+            })
+            .count()
+    }
+
+    /// Penalty for the orders whose articles are in more than one batch
+    pub fn split_cost(&self, batch_mapping: &BatchMapping) -> usize {
+        match self.cost_model.penalty_per_batch_split {
+            0 => 0,
+            penalty => penalty * self.num_split_orders(batch_mapping),
+        }
+    }
+
+    /// Counts the articles of every batch, for moving articles between batches
+    pub fn batching(&self, batch_mapping: BatchMapping) -> Batching<'_> {
+        Batching::new(self, batch_mapping)
+    }
+}
+
+/// A mapping from articles to batches, with counters per batch
+///
+/// Moving an article only updates the counters and costs of the two batches involved.
+#[derive(Debug, Clone)]
+pub struct Batching<'a> {
+    evaluator: &'a Evaluator<'a>,
+    batch_mapping: BatchMapping,
+    /// Counters by batch id
+    batches: Vec<BatchCounters>,
+    /// Cost of every batch, by batch id
+    costs: Vec<usize>,
+    cost: usize,
+    num_overweight_batches: usize,
+    /// Number of articles of every order per batch, by order id
+    orders: BTreeMap<ID, BTreeMap<BatchId, usize>>,
+    num_split_orders: usize,
+}
+
+impl<'a> Batching<'a> {
+    fn new(evaluator: &'a Evaluator<'a>, batch_mapping: BatchMapping) -> Batching<'a> {
+        let cost_model = evaluator.cost_model;
+        let mut batches = vec![BatchCounters::default(); evaluator.articles.len()];
+        for (article, batch_id) in evaluator.articles.iter().zip(batch_mapping.iter()) {
+            batches[*batch_id].add(article, cost_model);
+        }
+
+        let costs = batches
+            .iter()
+            .map(|batch| batch.cost(cost_model))
+            .collect::<Vec<_>>();
+        let num_overweight_batches = batches
+            .iter()
+            .filter(|batch| batch.volume > cost_model.max_weight_per_batch)
+            .count();
+
+        let mut orders: BTreeMap<ID, BTreeMap<BatchId, usize>> = BTreeMap::new();
+        for (article, batch_id) in evaluator.articles.iter().zip(batch_mapping.iter()) {
+            *orders
+                .entry(article.order_id)
+                .or_default()
+                .entry(*batch_id)
+                .or_default() += 1;
+        }
+        let num_split_orders = orders.values().filter(|batches| batches.len() > 1).count();
+
+        Batching {
+            evaluator,
+            batch_mapping,
+            batches,
+            cost: costs.iter().sum(),
+            costs,
+            num_overweight_batches,
+            orders,
+            num_split_orders,
+        }
+    }
+
+    pub fn batch_mapping(&self) -> &BatchMapping {
+        &self.batch_mapping
+    }
+
+    pub fn into_batch_mapping(self) -> BatchMapping {
+        self.batch_mapping
+    }
+
+    /// Tour cost plus rest cost of all batches plus the penalty for split orders, regardless of
+    /// the weight of the batches
+    pub fn cost(&self) -> usize {
+        self.cost + self.num_split_orders * self.cost_model().penalty_per_batch_split
+    }
+
+    /// Number of orders whose articles are in more than one batch
+    pub fn num_split_orders(&self) -> usize {
+        self.num_split_orders
+    }
+
+    /// Whether every batch stays within the max weight
+    pub fn is_valid(&self) -> bool {
+        self.num_overweight_batches == 0
+    }
+
+    pub fn cost_model(&self) -> &'a CostModel {
+        self.evaluator.cost_model
+    }
+
+    pub fn num_articles(&self) -> usize {
+        self.evaluator.num_articles()
+    }
+
+    pub fn article(&self, idx: usize) -> &'a OrderedArticle {
+        self.evaluator.article(idx)
+    }
+
+    pub fn batch_of(&self, idx: usize) -> BatchId {
+        self.batch_mapping[idx]
+    }
+
+    pub fn volume(&self, batch_id: BatchId) -> Volume {
+        self.batches[batch_id].volume
+    }
+
+    /// Tour cost plus rest cost of a single batch, 0 if it is empty
+    pub fn batch_cost(&self, batch_id: BatchId) -> usize {
+        self.costs[batch_id]
+    }
+
+    /// Whether the article fits into the batch without exceeding the max weight
+    pub fn fits(&self, idx: usize, batch_id: BatchId) -> bool {
+        self.volume(batch_id) + self.article(idx).volume <= self.cost_model().max_weight_per_batch
+    }
+
+    /// Whether the batch picks an article in the aisle, given as `(warehouse, aisle)`
+    pub fn visits_aisle(&self, batch_id: BatchId, aisle: (ID, ID)) -> bool {
+        self.batches[batch_id].aisles.contains_key(&aisle)
+    }
+
+    pub fn is_empty_batch(&self, batch_id: BatchId) -> bool {
+        self.batches[batch_id].num_articles == 0
+    }
+
+    /// First batch without articles
+    pub fn empty_batch_id(&self) -> Option<BatchId> {
+        (0..self.batches.len()).find(|batch_id| self.is_empty_batch(*batch_id))
+    }
+
+    /// Adds an empty batch with the next free id, past the ids of a batch mapping
+    pub fn add_batch(&mut self) -> BatchId {
+        self.batches.push(BatchCounters::default());
+        self.costs.push(0);
+        self.batches.len() - 1
+    }
+
+    pub fn non_empty_batch_ids(&self) -> Vec<BatchId> {
+        (0..self.batches.len())
+            .filter(|batch_id| self.batches[*batch_id].num_articles > 0)
+            .collect()
+    }
+
+    /// Indices of the articles of a batch
+    pub fn articles_of(&self, batch_id: BatchId) -> Vec<usize> {
+        (0..self.batch_mapping.len())
+            .filter(|idx| self.batch_mapping[*idx] == batch_id)
+            .collect()
+    }
+
+    /// Moves an article into another batch and updates the costs of both batches
+    pub fn move_article(&mut self, idx: usize, to: BatchId) {
+        let from = self.batch_mapping[idx];
+        if from == to {
+            return;
+        }
+
+        let article = self.article(idx);
+        let cost_model = self.cost_model();
+        self.update(from, |batch| batch.remove(article, cost_model));
+        self.update(to, |batch| batch.add(article, cost_model));
+        self.batch_mapping[idx] = to;
+
+        let batches = self
+            .orders
+            .get_mut(&article.order_id)
+            .expect("order of article");
+        let was_split = batches.len() > 1;
+        let count = batches.get_mut(&from).expect("article not in batch");
+        *count -= 1;
+        if *count == 0 {
+            batches.remove(&from);
+        }
+        *batches.entry(to).or_default() += 1;
+        let is_split = batches.len() > 1;
+        self.num_split_orders = self.num_split_orders + is_split as usize - was_split as usize;
+    }
+
+    /// Tour cost plus rest cost of a single batch that holds the articles of both batches
+    pub fn merged_cost(&self, batch_id: BatchId, other_batch_id: BatchId) -> usize {
+        let mut merged = self.batches[batch_id].clone();
+        merged.merge(&self.batches[other_batch_id], self.cost_model());
+        merged.cost(self.cost_model())
+    }
+
+    fn update(&mut self, batch_id: BatchId, change: impl FnOnce(&mut BatchCounters)) {
+        let cost_model = self.evaluator.cost_model;
+        let batch = &mut self.batches[batch_id];
+
+        let was_overweight = batch.volume > cost_model.max_weight_per_batch;
+        change(batch);
+        let is_overweight = batch.volume > cost_model.max_weight_per_batch;
+        let cost = batch.cost(cost_model);
+
+        self.num_overweight_batches =
+            self.num_overweight_batches + is_overweight as usize - was_overweight as usize;
+        self.cost = self.cost + cost - self.costs[batch_id];
+        self.costs[batch_id] = cost;
+    }
+}
+
+/// Warehouse, aisle and position of a location in one number, ordered like the triple
+type LocationKey = u64;
+
+fn location_key(location: &ArticleLocation) -> LocationKey {
+    (location.warehouse as u64) << 32 | (location.aisle as u64) << 16 | location.position as u64
+}
+
+/// Volume, visited aisles and tour cost of a single batch
+///
+/// Adding or removing an article only recalculates the cost of its aisle and its warehouse.
+#[derive(Debug, Clone, Default)]
+struct BatchCounters {
+    num_articles: usize,
+    volume: Volume,
+    /// Number of articles per position, by warehouse and aisle
+    aisles: BTreeMap<(ID, ID), BTreeMap<ID, usize>>,
+    /// Tour cost of the visited aisles, without the cost per batch
+    tour_cost: usize,
+}
+
+impl BatchCounters {
+    fn add(&mut self, article: &OrderedArticle, cost_model: &CostModel) {
+        self.num_articles += 1;
+        self.volume += article.volume;
+
+        let location = article.location;
+        let aisle = (location.warehouse, location.aisle);
+        self.update_aisle(aisle, cost_model, |positions| {
+            *positions.entry(location.position).or_default() += 1;
+        });
+    }
+
+    fn remove(&mut self, article: &OrderedArticle, cost_model: &CostModel) {
+        self.num_articles -= 1;
+        self.volume -= article.volume;
+
+        let location = article.location;
+        let aisle = (location.warehouse, location.aisle);
+        self.update_aisle(aisle, cost_model, |positions| {
+            let count = positions
+                .get_mut(&location.position)
+                .expect("article not in batch");
+            *count -= 1;
+            if *count == 0 {
+                positions.remove(&location.position);
+            }
+        });
+    }
+
+    fn merge(&mut self, other: &BatchCounters, cost_model: &CostModel) {
+        self.num_articles += other.num_articles;
+        self.volume += other.volume;
+        for (aisle, other_positions) in other.aisles.iter() {
+            self.update_aisle(*aisle, cost_model, |positions| {
+                for (position, count) in other_positions.iter() {
+                    *positions.entry(*position).or_default() += count;
+                }
+            });
+        }
+    }
+
+    /// Changes the positions of an aisle and replaces the cost of the aisle and its warehouse
+    fn update_aisle(
+        &mut self,
+        aisle: (ID, ID),
+        cost_model: &CostModel,
+        change: impl FnOnce(&mut BTreeMap<ID, usize>),
+    ) {
+        let (warehouse, _) = aisle;
+        self.tour_cost -=
+            self.warehouse_cost(warehouse, cost_model) + self.aisle_cost(aisle, cost_model);
+
+        let positions = self.aisles.entry(aisle).or_default();
+        change(positions);
+        if positions.is_empty() {
+            self.aisles.remove(&aisle);
+        }
+
+        self.tour_cost +=
+            self.warehouse_cost(warehouse, cost_model) + self.aisle_cost(aisle, cost_model);
+    }
+
+    /// Cost of an aisle down to its deepest position, 0 if the batch does not visit it
+    fn aisle_cost(&self, aisle: (ID, ID), cost_model: &CostModel) -> usize {
+        self.aisles
+            .get(&aisle)
+            .and_then(|positions| positions.last_key_value())
+            .map_or(0, |(depth, _)| cost_model.aisle_cost(*depth))
+    }
+
+    /// Cost of a warehouse up to its farthest aisle, 0 if the batch does not visit it
+    fn warehouse_cost(&self, warehouse: ID, cost_model: &CostModel) -> usize {
+        self.aisles
+            .range((warehouse, 0)..=(warehouse, ID::MAX))
+            .next_back()
+            .map_or(0, |((_, aisle), _)| cost_model.warehouse_cost(*aisle))
+    }
+
+    /// Tour cost plus rest cost, 0 if the batch is empty
+    fn cost(&self, cost_model: &CostModel) -> usize {
+        if self.num_articles == 0 {
+            return 0;
+        }
+
+        // every batch pays its cost twice: as part of its tour and as rest cost
+        self.tour_cost + 2 * cost_model.cost_per_batch
+    }
+}
@@ -0,0 +1,198 @@
+//! Lower bounds of the cost of any solution, which tell how far a solution is at most from the
+//! optimum
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cost::{CostModel, TourModel};
+use crate::model::*;
+
+/// Lower bound of the number of bins of the given capacity that hold all volumes
+///
+/// The bound L2 of Martello and Toth: items above half the capacity need a bin each, and the
+/// smaller items fill at most the room these bins leave. Never below the total volume divided by
+/// the capacity.
+pub fn bin_packing_bound(volumes: &[Volume], capacity: Volume) -> usize {
+    if volumes.is_empty() {
+        return 0;
+    }
+    if capacity == 0 {
+        return volumes.len();
+    }
+
+    let total = volumes.iter().sum::<Volume>();
+    let alphas = volumes
+        .iter()
+        .copied()
+        .filter(|volume| 2 * volume <= capacity)
+        .chain([0])
+        .collect::<BTreeSet<_>>();
+
+    alphas
+        .into_iter()
+        .map(|alpha| {
+            // items that share a bin with no item of at least alpha
+            let mut large = 0;
+            // items above half the capacity, and their volume
+            let (mut medium, mut medium_volume) = (0, 0);
+            // items of at least alpha up to half the capacity
+            let mut small_volume = 0;
+            for volume in volumes.iter().copied() {
+                if volume > capacity - alpha {
+                    large += 1;
+                } else if 2 * volume > capacity {
+                    medium += 1;
+                    medium_volume += volume;
+                } else if volume >= alpha {
+                    small_volume += volume;
+                }
+            }
+
+            let room = medium * capacity - medium_volume;
+            large + medium + small_volume.saturating_sub(room).div_ceil(capacity)
+        })
+        .max()
+        .unwrap_or(0)
+        .max(total.div_ceil(capacity))
+}
+
+/// Lower bounds of the numbers of batches, waives and visits, and of the costs of a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LowerBound {
+    /// Min number of batches, by packing the volumes into batches of the max weight
+    pub num_batches: usize,
+    /// Min number of waives, by the number of articles
+    pub num_waives: usize,
+    /// Min number of batches that visit a warehouse, by warehouse id
+    pub warehouse_visits: BTreeMap<ID, usize>,
+    /// Min number of batches that visit an aisle, by warehouse and aisle id
+    pub aisle_visits: BTreeMap<(ID, ID), usize>,
+    pub tour_cost: usize,
+    pub rest_cost_batches: usize,
+    pub rest_cost_waives: usize,
+}
+
+impl LowerBound {
+    /// Bounds every count and cost of the model on its own
+    ///
+    /// The articles of a warehouse or an aisle need at least as many visits as batches that hold
+    /// their volumes. Penalties for split orders are bounded by 0.
+    pub fn new(model: &Model) -> LowerBound {
+        let cost_model = model.cost_model();
+        let articles = model.get_ordered_articles();
+        let max_items = model.max_items_per_batch().max(1);
+        let min_batches = |volumes: &[Volume]| {
+            bin_packing_bound(volumes, cost_model.max_weight_per_batch)
+                .max(volumes.len().div_ceil(max_items))
+        };
+
+        let mut warehouse_volumes: BTreeMap<ID, Vec<Volume>> = BTreeMap::new();
+        let mut aisle_volumes: BTreeMap<(ID, ID), Vec<Volume>> = BTreeMap::new();
+        for article in articles.iter() {
+            let location = article.location;
+            warehouse_volumes
+                .entry(location.warehouse)
+                .or_default()
+                .push(article.volume);
+            aisle_volumes
+                .entry((location.warehouse, location.aisle))
+                .or_default()
+                .push(article.volume);
+        }
+        let warehouse_visits = warehouse_volumes
+            .iter()
+            .map(|(warehouse, volumes)| (*warehouse, min_batches(volumes)))
+            .collect::<BTreeMap<_, _>>();
+        let aisle_visits = aisle_volumes
+            .iter()
+            .map(|(aisle, volumes)| (*aisle, min_batches(volumes)))
+            .collect::<BTreeMap<_, _>>();
+
+        let volumes = articles
+            .iter()
+            .map(|article| article.volume)
+            .collect::<Vec<_>>();
+        let num_batches = warehouse_visits
+            .values()
+            .copied()
+            .fold(min_batches(&volumes), usize::max);
+        let num_waives = articles
+            .len()
+            .div_ceil(cost_model.max_articles_per_waive.max(1));
+
+        let tour_cost = num_batches * cost_model.cost_per_batch
+            + visits_cost(cost_model, &articles, &warehouse_visits, &aisle_visits);
+
+        LowerBound {
+            num_batches,
+            num_waives,
+            warehouse_visits,
+            aisle_visits,
+            tour_cost,
+            rest_cost_batches: num_batches * cost_model.cost_per_batch,
+            rest_cost_waives: num_waives * cost_model.cost_per_waive,
+        }
+    }
+
+    /// Lower bound of the tour cost plus the rest cost of any batching
+    pub fn batch_cost(&self) -> usize {
+        self.tour_cost + self.rest_cost_batches
+    }
+
+    pub fn overall_cost(&self) -> usize {
+        self.batch_cost() + self.rest_cost_waives
+    }
+}
+
+/// Cost of visiting every warehouse and aisle the given number of times
+///
+/// In the distance tour model, a single visit walks to the farthest aisle of a warehouse and to the
+/// deepest position of an aisle, the others at least to the front position of the aisle.
+fn visits_cost(
+    cost_model: &CostModel,
+    articles: &[&OrderedArticle],
+    warehouse_visits: &BTreeMap<ID, usize>,
+    aisle_visits: &BTreeMap<(ID, ID), usize>,
+) -> usize {
+    let warehouses_cost = warehouse_visits
+        .values()
+        .map(|visits| visits * cost_model.cost_per_warehouse)
+        .sum::<usize>();
+
+    match cost_model.tour_model {
+        TourModel::Counting => {
+            warehouses_cost
+                + aisle_visits
+                    .values()
+                    .map(|visits| visits * cost_model.cost_per_aisle)
+                    .sum::<usize>()
+        }
+        TourModel::Distance => {
+            let mut farthest_aisles: BTreeMap<ID, ID> = BTreeMap::new();
+            // front and deepest position of every aisle
+            let mut positions: BTreeMap<(ID, ID), (ID, ID)> = BTreeMap::new();
+            for article in articles.iter() {
+                let location = article.location;
+                let farthest_aisle = farthest_aisles.entry(location.warehouse).or_default();
+                *farthest_aisle = (*farthest_aisle).max(location.aisle);
+                let (front, deepest) = positions
+                    .entry((location.warehouse, location.aisle))
+                    .or_insert((location.position, location.position));
+                *front = (*front).min(location.position);
+                *deepest = (*deepest).max(location.position);
+            }
+
+            let walk = |position: ID| 2 * (position as usize + 1) * cost_model.cost_per_position;
+            warehouses_cost
+                + farthest_aisles
+                    .values()
+                    .map(|aisle| 2 * *aisle as usize * cost_model.cost_per_aisle_step)
+                    .sum::<usize>()
+                + aisle_visits
+                    .iter()
+                    .map(|(aisle, visits)| {
+                        let (front, deepest) = positions[aisle];
+                        walk(deepest) + (visits - 1) * walk(front)
+                    })
+                    .sum::<usize>()
+        }
+    }
+}
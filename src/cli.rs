@@ -1,10 +1,29 @@
-use clap::Parser;
+use anyhow::Result;
+use clap::builder::RangedU64ValueParser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use order_batching::ga::MIN_POPULATION_SIZE;
+use order_batching::heuristics::alns::AlnsParams;
+use order_batching::heuristics::annealing::{Cooling, SaMoves, SaParams};
+use order_batching::heuristics::tabu::{Aspiration, TabuParams};
+use order_batching::{
+    load_cost_model, Algorithm, BatchEncoding, CostModel, Infeasible, RoutingPolicy, TourModel,
+    WaiveSearch,
+};
 
 #[derive(Parser)]
-#[clap(author, version, about)]
+#[clap(
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub(crate) struct Cli {
-    #[clap(value_parser)]
-    pub(crate) input_file: String,
+    #[clap(subcommand)]
+    pub(crate) command: Option<Command>,
+
+    #[clap(value_parser, required = true)]
+    pub(crate) input_file: Option<String>,
 
     #[clap(value_parser)]
     pub(crate) output_file: Option<String>,
@@ -13,6 +32,7 @@ pub(crate) struct Cli {
         long = "batch-population",
         alias = "bp",
         default_value_t = 100,
+        value_parser = population_parser(),
         help = "Initial size of orders<->batches population"
     )]
     pub(crate) num_batch_individuals: usize,
@@ -29,6 +49,7 @@ pub(crate) struct Cli {
         long = "waive-population",
         alias = "wp",
         default_value_t = 100,
+        value_parser = population_parser(),
         help = "Initial size of batches<->waives population"
     )]
     pub(crate) num_waive_individuals: usize,
@@ -43,4 +64,593 @@ pub(crate) struct Cli {
 
     #[clap(long, action)]
     pub(crate) no_output: bool,
+
+    #[clap(long, help = "Seed for a reproducible run (random if not given)")]
+    pub(crate) seed: Option<u64>,
+
+    #[clap(
+        long = "time-limit",
+        help = "Max runtime of the search in seconds, split between both stages"
+    )]
+    pub(crate) time_limit_secs: Option<f64>,
+
+    #[clap(
+        long = "batch-stagnation",
+        help = "Stop orders<->batches after this many generations without improvement"
+    )]
+    pub(crate) batch_stagnation_generations: Option<usize>,
+
+    #[clap(
+        long = "waive-stagnation",
+        help = "Stop batches<->waives after this many generations without improvement"
+    )]
+    pub(crate) waive_stagnation_generations: Option<usize>,
+
+    #[clap(
+        long = "stagnation-epsilon",
+        default_value_t = 0.0,
+        help = "Min relative improvement of the best fitness that resets the stagnation counter"
+    )]
+    pub(crate) stagnation_epsilon: f64,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = AlgorithmArg::Ga,
+        help = "Search strategy for batches and waives"
+    )]
+    pub(crate) algorithm: AlgorithmArg,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = BatchEncodingArg::Articles,
+        help = "Genes of the orders<->batches search"
+    )]
+    pub(crate) batch_encoding: BatchEncodingArg,
+
+    #[clap(
+        long = "routing",
+        value_enum,
+        default_value_t = RoutingPolicyArg::Return,
+        help = "Routing policy that orders the items of each batch"
+    )]
+    pub(crate) routing_policy: RoutingPolicyArg,
+
+    #[clap(
+        long,
+        default_value_t = 1,
+        help = "Number of threads, each evolving its own population (island model)"
+    )]
+    pub(crate) threads: usize,
+
+    #[clap(long, default_value_t = 10, help = "Generations between migrations of the islands")]
+    pub(crate) migration_interval: usize,
+
+    #[clap(long, action, help = "Skip the local search on the found batches")]
+    pub(crate) no_local_search: bool,
+
+    #[clap(
+        long,
+        action,
+        help = "Never split an order across waives, merging waives that share an order"
+    )]
+    pub(crate) strict_waives: bool,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = InfeasibleArg::Repair,
+        help = "How the genetic searches rank overweight batches and oversized waives"
+    )]
+    pub(crate) infeasible: InfeasibleArg,
+
+    #[clap(flatten)]
+    pub(crate) annealing: AnnealingArgs,
+
+    #[clap(flatten)]
+    pub(crate) alns: AlnsArgs,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = WaiveSearchArg::Ga,
+        help = "Search for the waives of the found batches (--algorithm ga)"
+    )]
+    pub(crate) waive_search: WaiveSearchArg,
+
+    #[clap(flatten)]
+    pub(crate) tabu: TabuArgs,
+
+    #[clap(
+        long,
+        default_value_t = 1000,
+        help = "Max number of linear programs solved by --algorithm exact"
+    )]
+    pub(crate) exact_node_limit: usize,
+
+    #[clap(
+        long,
+        help = "Write the mixed integer program of the input to a .lp or .mps file"
+    )]
+    pub(crate) export_model: Option<String>,
+
+    #[clap(flatten)]
+    pub(crate) cost_model: CostModelArgs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum AlgorithmArg {
+    /// Genetic search, seeded with the greedy solution
+    Ga,
+    /// Greedy construction only
+    Greedy,
+    /// Single genetic search over batches and waives together, using the batch settings
+    Joint,
+    /// Simulated annealing of the greedy batches, then of the greedy waives
+    Sa,
+    /// Adaptive large neighborhood search of the greedy batches, then greedy waives
+    Alns,
+    /// Branch and bound over a mixed integer program, proves optimality for tiny inputs only
+    Exact,
+}
+
+impl From<AlgorithmArg> for Algorithm {
+    fn from(algorithm: AlgorithmArg) -> Self {
+        match algorithm {
+            AlgorithmArg::Ga => Algorithm::Ga,
+            AlgorithmArg::Greedy => Algorithm::Greedy,
+            AlgorithmArg::Joint => Algorithm::Joint,
+            AlgorithmArg::Sa => Algorithm::Sa,
+            AlgorithmArg::Alns => Algorithm::Alns,
+            AlgorithmArg::Exact => Algorithm::Exact,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum BatchEncodingArg {
+    /// A gene per ordered article, orders may be split over batches
+    Articles,
+    /// A gene per order, only orders exceeding a batch are split
+    Orders,
+}
+
+impl From<BatchEncodingArg> for BatchEncoding {
+    fn from(batch_encoding: BatchEncodingArg) -> Self {
+        match batch_encoding {
+            BatchEncodingArg::Articles => BatchEncoding::Articles,
+            BatchEncodingArg::Orders => BatchEncoding::Orders,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum InfeasibleArg {
+    /// Rank them lowest
+    Reject,
+    /// Move the overflowing articles or batches elsewhere before ranking
+    Repair,
+    /// Add a penalty per unit of overflow, repairing only the result
+    Penalize,
+}
+
+impl From<InfeasibleArg> for Infeasible {
+    fn from(infeasible: InfeasibleArg) -> Self {
+        match infeasible {
+            InfeasibleArg::Reject => Infeasible::Reject,
+            InfeasibleArg::Repair => Infeasible::Repair,
+            InfeasibleArg::Penalize => Infeasible::Penalize,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum WaiveSearchArg {
+    /// Genetic search, seeded with the greedy waives
+    Ga,
+    /// Tabu search, starting from the greedy waives
+    Tabu,
+}
+
+impl From<WaiveSearchArg> for WaiveSearch {
+    fn from(waive_search: WaiveSearchArg) -> Self {
+        match waive_search {
+            WaiveSearchArg::Ga => WaiveSearch::Ga,
+            WaiveSearchArg::Tabu => WaiveSearch::Tabu,
+        }
+    }
+}
+
+#[derive(Args)]
+pub(crate) struct TabuArgs {
+    #[clap(
+        long,
+        default_value_t = 200,
+        help = "Max iterations of --waive-search tabu"
+    )]
+    pub(crate) tabu_iterations: usize,
+
+    #[clap(
+        long,
+        default_value_t = 10,
+        help = "Iterations a batch may not return into the waive it left"
+    )]
+    pub(crate) tabu_tenure: usize,
+
+    #[clap(
+        long,
+        default_value_t = 5,
+        help = "Max iterations added to the tabu tenure at random"
+    )]
+    pub(crate) tabu_tenure_spread: usize,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = AspirationArg::Best,
+        help = "When a tabu change may be made anyway"
+    )]
+    pub(crate) tabu_aspiration: AspirationArg,
+
+    #[clap(long, action, help = "Only move single batches, never swap two")]
+    pub(crate) tabu_no_swaps: bool,
+
+    #[clap(
+        long,
+        default_value_t = 50,
+        help = "Stop the tabu search after this many iterations without improvement"
+    )]
+    pub(crate) tabu_stagnation: usize,
+}
+
+impl TabuArgs {
+    /// Parameters of the tabu search, the solver fills in seed and deadline
+    pub(crate) fn params(&self) -> TabuParams {
+        TabuParams {
+            iterations: self.tabu_iterations,
+            tenure: self.tabu_tenure,
+            tenure_spread: self.tabu_tenure_spread,
+            aspiration: self.tabu_aspiration.into(),
+            swaps: !self.tabu_no_swaps,
+            stagnation: Some(self.tabu_stagnation),
+            ..TabuParams::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum AspirationArg {
+    /// If it leads to a new best solution
+    Best,
+    /// Never
+    None,
+}
+
+impl From<AspirationArg> for Aspiration {
+    fn from(aspiration: AspirationArg) -> Self {
+        match aspiration {
+            AspirationArg::Best => Aspiration::BestCost,
+            AspirationArg::None => Aspiration::None,
+        }
+    }
+}
+
+#[derive(Args)]
+pub(crate) struct AnnealingArgs {
+    #[clap(
+        long,
+        default_value_t = 10.0,
+        help = "Start temperature of --algorithm sa"
+    )]
+    pub(crate) sa_start_temperature: f64,
+
+    #[clap(
+        long,
+        default_value_t = 0.1,
+        help = "End temperature of --algorithm sa"
+    )]
+    pub(crate) sa_end_temperature: f64,
+
+    #[clap(
+        long,
+        default_value_t = 100,
+        help = "Number of temperatures of --algorithm sa"
+    )]
+    pub(crate) sa_steps: usize,
+
+    #[clap(
+        long,
+        default_value_t = 100,
+        help = "Changes tried per temperature of --algorithm sa"
+    )]
+    pub(crate) sa_moves_per_step: usize,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = CoolingArg::Geometric,
+        help = "Cooling schedule of --algorithm sa"
+    )]
+    pub(crate) sa_cooling: CoolingArg,
+
+    #[clap(
+        long,
+        value_enum,
+        default_value_t = SaMovesArg::Both,
+        help = "Neighborhood of --algorithm sa"
+    )]
+    pub(crate) sa_moves: SaMovesArg,
+}
+
+impl AnnealingArgs {
+    /// Parameters of the simulated annealing, the solver fills in seed and deadline
+    pub(crate) fn params(&self) -> SaParams {
+        SaParams {
+            start_temperature: self.sa_start_temperature,
+            end_temperature: self.sa_end_temperature,
+            temperature_steps: self.sa_steps,
+            moves_per_temperature: self.sa_moves_per_step,
+            cooling: self.sa_cooling.into(),
+            moves: self.sa_moves.into(),
+            ..SaParams::default()
+        }
+    }
+}
+
+#[derive(Args)]
+pub(crate) struct AlnsArgs {
+    #[clap(
+        long,
+        default_value_t = 1000,
+        help = "Destroy and repair steps of --algorithm alns"
+    )]
+    pub(crate) alns_iterations: usize,
+
+    #[clap(
+        long,
+        default_value_t = 0.1,
+        help = "Max share of the articles removed per step of --algorithm alns"
+    )]
+    pub(crate) alns_destroy_fraction: f64,
+
+    #[clap(
+        long,
+        default_value_t = 50,
+        help = "Steps between updates of the operator weights of --algorithm alns"
+    )]
+    pub(crate) alns_segment: usize,
+
+    #[clap(
+        long,
+        default_value_t = 0.2,
+        help = "How fast the operator weights of --algorithm alns adapt (0 to 1)"
+    )]
+    pub(crate) alns_reaction: f64,
+}
+
+impl AlnsArgs {
+    /// Parameters of the ALNS, the solver fills in seed and deadline
+    pub(crate) fn params(&self) -> AlnsParams {
+        AlnsParams {
+            iterations: self.alns_iterations,
+            destroy_fraction: self.alns_destroy_fraction,
+            segment_length: self.alns_segment,
+            reaction: self.alns_reaction,
+            ..AlnsParams::default()
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum CoolingArg {
+    /// Multiply the temperature by the same factor at every step
+    Geometric,
+    /// Lower the temperature by the same amount at every step
+    Linear,
+}
+
+impl From<CoolingArg> for Cooling {
+    fn from(cooling: CoolingArg) -> Self {
+        match cooling {
+            CoolingArg::Geometric => Cooling::Geometric,
+            CoolingArg::Linear => Cooling::Linear,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum SaMovesArg {
+    /// Move an article into another batch, or a batch into another waive
+    Move,
+    /// Swap two articles of different batches, or two batches of different waives
+    Swap,
+    /// Moves and swaps
+    Both,
+}
+
+impl From<SaMovesArg> for SaMoves {
+    fn from(moves: SaMovesArg) -> Self {
+        match moves {
+            SaMovesArg::Move => SaMoves::Move,
+            SaMovesArg::Swap => SaMoves::Swap,
+            SaMovesArg::Both => SaMoves::Both,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum RoutingPolicyArg {
+    /// Enter every aisle from the front and return
+    Return,
+    /// Traverse every aisle in alternating directions
+    SShape,
+    /// Enter aisles from both sides, leaving out the largest gap
+    LargestGap,
+    /// Traverse or return per aisle, whichever is shorter overall
+    OptimalWithinAisle,
+}
+
+impl From<RoutingPolicyArg> for RoutingPolicy {
+    fn from(routing_policy: RoutingPolicyArg) -> Self {
+        match routing_policy {
+            RoutingPolicyArg::Return => RoutingPolicy::Return,
+            RoutingPolicyArg::SShape => RoutingPolicy::SShape,
+            RoutingPolicyArg::LargestGap => RoutingPolicy::LargestGap,
+            RoutingPolicyArg::OptimalWithinAisle => RoutingPolicy::OptimalWithinAisle,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub(crate) enum Command {
+    /// Checks and scores an existing solution file
+    Validate(ValidateArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct ValidateArgs {
+    #[clap(value_parser)]
+    pub(crate) input_file: String,
+
+    #[clap(value_parser, help = "Solution to check, in the same format as the output")]
+    pub(crate) solution_file: String,
+
+    #[clap(flatten)]
+    pub(crate) cost_model: CostModelArgs,
+}
+
+#[derive(Args)]
+pub(crate) struct CostModelArgs {
+    #[clap(
+        long = "cost-model",
+        help = "JSON or TOML file with capacities and costs (defaults to the task's values)"
+    )]
+    pub(crate) cost_model_file: Option<String>,
+
+    #[clap(long, help = "Max volume of a single batch")]
+    pub(crate) max_weight_per_batch: Option<usize>,
+
+    #[clap(long, help = "Max number of articles in a single waive")]
+    pub(crate) max_articles_per_waive: Option<usize>,
+
+    #[clap(long, help = "Cost of a single waive")]
+    pub(crate) cost_per_waive: Option<usize>,
+
+    #[clap(long, help = "Cost of a single batch")]
+    pub(crate) cost_per_batch: Option<usize>,
+
+    #[clap(long, help = "Cost of visiting a warehouse during a batch's tour")]
+    pub(crate) cost_per_warehouse: Option<usize>,
+
+    #[clap(long, help = "Cost of visiting an aisle during a batch's tour")]
+    pub(crate) cost_per_aisle: Option<usize>,
+
+    #[clap(long, value_enum, help = "How the tour cost of a batch is calculated")]
+    pub(crate) tour_model: Option<TourModelArg>,
+
+    #[clap(long, help = "Number of positions per aisle (distance tour model)")]
+    pub(crate) aisle_length: Option<usize>,
+
+    #[clap(long, help = "Cost of walking between neighboring positions (distance tour model)")]
+    pub(crate) cost_per_position: Option<usize>,
+
+    #[clap(long, help = "Cost of walking between neighboring aisles (distance tour model)")]
+    pub(crate) cost_per_aisle_step: Option<usize>,
+
+    #[clap(long, help = "Soft penalty per order split over several waives (guides the search)")]
+    pub(crate) penalty_per_split_order: Option<usize>,
+
+    #[clap(long, help = "Penalty per order picked in more than one batch (part of the cost)")]
+    pub(crate) penalty_per_batch_split: Option<usize>,
+
+    #[clap(long, help = "Penalty per order picked in more than one waive (part of the cost)")]
+    pub(crate) penalty_per_waive_split: Option<usize>,
+
+    #[clap(long, help = "Penalty per volume above the max weight (--infeasible penalize)")]
+    pub(crate) penalty_per_overweight: Option<usize>,
+
+    #[clap(long, help = "Penalty per article above the max of a waive (--infeasible penalize)")]
+    pub(crate) penalty_per_excess_article: Option<usize>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum TourModelArg {
+    /// Counts the visited warehouses and aisles
+    Counting,
+    /// Walking distance based on the positions of the articles
+    Distance,
+}
+
+impl From<TourModelArg> for TourModel {
+    fn from(tour_model: TourModelArg) -> Self {
+        match tour_model {
+            TourModelArg::Counting => TourModel::Counting,
+            TourModelArg::Distance => TourModel::Distance,
+        }
+    }
+}
+
+impl CostModelArgs {
+    /// Builds the cost model from the cost model file (if any), overridden by single options
+    pub(crate) fn cost_model(&self) -> Result<CostModel> {
+        let mut cost_model = match &self.cost_model_file {
+            Some(file_path) => load_cost_model(file_path)?,
+            None => CostModel::default(),
+        };
+
+        if let Some(value) = self.max_weight_per_batch {
+            cost_model.max_weight_per_batch = value;
+        }
+        if let Some(value) = self.max_articles_per_waive {
+            cost_model.max_articles_per_waive = value;
+        }
+        if let Some(value) = self.cost_per_waive {
+            cost_model.cost_per_waive = value;
+        }
+        if let Some(value) = self.cost_per_batch {
+            cost_model.cost_per_batch = value;
+        }
+        if let Some(value) = self.cost_per_warehouse {
+            cost_model.cost_per_warehouse = value;
+        }
+        if let Some(value) = self.cost_per_aisle {
+            cost_model.cost_per_aisle = value;
+        }
+        if let Some(value) = self.tour_model {
+            cost_model.tour_model = value.into();
+        }
+        if let Some(value) = self.aisle_length {
+            cost_model.aisle_length = value;
+        }
+        if let Some(value) = self.cost_per_position {
+            cost_model.cost_per_position = value;
+        }
+        if let Some(value) = self.cost_per_aisle_step {
+            cost_model.cost_per_aisle_step = value;
+        }
+        if let Some(value) = self.penalty_per_split_order {
+            cost_model.penalty_per_split_order = value;
+        }
+        if let Some(value) = self.penalty_per_batch_split {
+            cost_model.penalty_per_batch_split = value;
+        }
+        if let Some(value) = self.penalty_per_waive_split {
+            cost_model.penalty_per_waive_split = value;
+        }
+        if let Some(value) = self.penalty_per_overweight {
+            cost_model.penalty_per_overweight = value;
+        }
+        if let Some(value) = self.penalty_per_excess_article {
+            cost_model.penalty_per_excess_article = value;
+        }
+
+        cost_model.validate()?;
+        Ok(cost_model)
+    }
+}
+
+/// Accepts populations that a genetic search can run with
+fn population_parser() -> RangedU64ValueParser<usize> {
+    RangedU64ValueParser::new().range(MIN_POPULATION_SIZE as u64..)
 }
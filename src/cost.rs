@@ -0,0 +1,189 @@
+//! Capacities and prices that define the cost of a solution
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+
+use crate::input::InputError;
+use crate::model::*;
+
+/// Limits for batches and waives and the cost of each picking step
+///
+/// Missing keys in a cost model file fall back to the values of the original task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CostModel {
+    pub max_weight_per_batch: Volume,
+    pub max_articles_per_waive: usize,
+    pub cost_per_waive: usize,
+    pub cost_per_batch: usize,
+    pub cost_per_warehouse: usize,
+    /// Cost per visited aisle, only used by [`TourModel::Counting`]
+    pub cost_per_aisle: usize,
+    pub tour_model: TourModel,
+    /// Number of positions per aisle, only used by [`TourModel::Distance`]
+    pub aisle_length: usize,
+    /// Cost of walking from one position of an aisle to the next, only used by
+    /// [`TourModel::Distance`]
+    pub cost_per_position: usize,
+    /// Cost of walking from one aisle to the next along the front cross aisle, only used by
+    /// [`TourModel::Distance`]
+    pub cost_per_aisle_step: usize,
+    /// Soft penalty per order that is split over several waives
+    ///
+    /// Only guides the search towards solutions that keep orders together, it is not part of the
+    /// reported cost.
+    pub penalty_per_split_order: usize,
+    /// Penalty per order that is picked in more than one batch, part of the reported cost
+    pub penalty_per_batch_split: usize,
+    /// Penalty per order that is picked in more than one waive, part of the reported cost
+    pub penalty_per_waive_split: usize,
+    /// Penalty per unit of volume above the max weight of a batch
+    ///
+    /// Only ranks infeasible solutions during a search with [`crate::ga::Infeasible::Penalize`].
+    pub penalty_per_overweight: usize,
+    /// Penalty per article above the max articles of a waive
+    ///
+    /// Only ranks infeasible solutions during a search with [`crate::ga::Infeasible::Penalize`].
+    pub penalty_per_excess_article: usize,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            max_weight_per_batch: MAX_WEIGHT_PER_BATCH,
+            max_articles_per_waive: MAX_ARTICLES_PER_WAIVE,
+            cost_per_waive: COST_PER_WAIVE,
+            cost_per_batch: COST_PER_BATCH,
+            cost_per_warehouse: COST_PER_WAREHOUSE,
+            cost_per_aisle: COST_PER_AISLE,
+            tour_model: TourModel::default(),
+            aisle_length: 20,
+            cost_per_position: 1,
+            cost_per_aisle_step: 3,
+            penalty_per_split_order: 1,
+            penalty_per_batch_split: 0,
+            penalty_per_waive_split: 0,
+            penalty_per_overweight: 1,
+            penalty_per_excess_article: COST_PER_WAIVE,
+        }
+    }
+}
+
+impl CostModel {
+    /// Checks that every batch and every waive can hold at least one article
+    pub fn validate(&self) -> Result<(), InputError> {
+        if self.max_weight_per_batch == 0 {
+            return Err(InputError::ZeroCapacity {
+                capacity: "max_weight_per_batch",
+            });
+        }
+        if self.max_articles_per_waive == 0 {
+            return Err(InputError::ZeroCapacity {
+                capacity: "max_articles_per_waive",
+            });
+        }
+        Ok(())
+    }
+
+    /// Cost of the tour that picks articles at the given locations, without the cost per batch
+    pub fn picking_cost<'a>(
+        &self,
+        locations: impl IntoIterator<Item = &'a ArticleLocation>,
+    ) -> usize {
+        let mut depths: BTreeMap<(ID, ID), ID> = BTreeMap::new();
+        locations.into_iter().for_each(|location| {
+            let depth = depths
+                .entry((location.warehouse, location.aisle))
+                .or_insert(location.position);
+            *depth = (*depth).max(location.position);
+        });
+
+        self.aisles_cost(depths)
+    }
+
+    /// Cost of the tour that visits the given aisles, without the cost per batch
+    ///
+    /// Expects every visited aisle once, as `(warehouse, aisle)` with its deepest visited position,
+    /// sorted by warehouse and aisle.
+    pub(crate) fn aisles_cost(&self, depths: impl IntoIterator<Item = ((ID, ID), ID)>) -> usize {
+        let mut cost = 0;
+        // warehouse and aisle of the previous visited aisle
+        let mut previous: Option<(ID, ID)> = None;
+
+        for ((warehouse, aisle), depth) in depths {
+            // the previous aisle is the farthest one of its warehouse
+            match previous {
+                Some((previous_warehouse, farthest_aisle)) if previous_warehouse != warehouse => {
+                    cost += self.warehouse_cost(farthest_aisle);
+                }
+                _ => {}
+            }
+            cost += self.aisle_cost(depth);
+            previous = Some((warehouse, aisle));
+        }
+        if let Some((_, farthest_aisle)) = previous {
+            cost += self.warehouse_cost(farthest_aisle);
+        }
+
+        cost
+    }
+
+    /// Cost of visiting a warehouse up to its farthest visited aisle, without the aisles themselves
+    pub(crate) fn warehouse_cost(&self, farthest_aisle: ID) -> usize {
+        self.cost_per_warehouse + self.cross_aisle_cost(farthest_aisle)
+    }
+
+    /// Cost of visiting an aisle down to its deepest visited position
+    pub(crate) fn aisle_cost(&self, depth: ID) -> usize {
+        match self.tour_model {
+            TourModel::Counting => self.cost_per_aisle,
+            TourModel::Distance => 2 * (depth as usize + 1) * self.cost_per_position,
+        }
+    }
+
+    /// Walk along the front cross aisle from aisle 0 up to the farthest visited aisle and back
+    fn cross_aisle_cost(&self, farthest_aisle: ID) -> usize {
+        match self.tour_model {
+            TourModel::Counting => 0,
+            TourModel::Distance => 2 * farthest_aisle as usize * self.cost_per_aisle_step,
+        }
+    }
+}
+
+/// How the tour cost of a batch is calculated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TourModel {
+    /// Counts the visited warehouses and aisles, as given by the original task
+    #[default]
+    Counting,
+    /// Sums up the walking distance through every visited warehouse, based on the positions of the
+    /// articles in their aisles
+    Distance,
+}
+
+/// Loads a cost model from a JSON or TOML file, depending on the file extension
+pub fn load_cost_model(file_path: &str) -> Result<CostModel> {
+    let content = std::fs::read_to_string(file_path)
+        .with_context(|| format!("cannot read cost model file {}", file_path))?;
+
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str());
+    let cost_model: CostModel = match extension {
+        Some("json") => json::from_str(&content).context("cannot deserialize cost model")?,
+        Some("toml") => toml::from_str(&content).context("cannot deserialize cost model")?,
+        _ => bail!(
+            "cannot detect format of cost model file {} (expected .json or .toml)",
+            file_path
+        ),
+    };
+    cost_model
+        .validate()
+        .with_context(|| format!("invalid cost model file {}", file_path))?;
+
+    Ok(cost_model)
+}
@@ -0,0 +1,134 @@
+//! Depth-first branch and bound over the linear relaxation of a formulation
+use std::time::Instant;
+
+use microlp::{Error, Solution};
+
+use super::{ExactError, ExactParams, ExactStats, ExactStatus, Formulation};
+
+/// Tolerance of integral values and of objective values
+const EPSILON: f64 = 1e-6;
+
+/// An open node: the relaxation of its parent and the variable to fix
+struct Node {
+    parent: Solution,
+    /// Variable index and value, `None` for the root
+    fixing: Option<(usize, f64)>,
+    /// Objective of the parent's relaxation, a lower bound of the node
+    bound: f64,
+}
+
+/// Searches an integral solution cheaper than `cost`, returns its variable values if it finds one
+///
+/// Dives into the child that rounds the most fractional variable first, deciding the assignments
+/// of articles and batches before anything else. Prunes nodes whose relaxation cannot beat the
+/// best solution, as every cost is integral.
+pub(super) fn solve(
+    formulation: &Formulation,
+    cost: usize,
+    params: ExactParams,
+) -> Result<(Option<Vec<f64>>, ExactStats), ExactError> {
+    let (problem, variables) = formulation.relaxation();
+    let root = match problem.solve() {
+        Ok(root) => root,
+        Err(Error::Infeasible) => {
+            return Ok((None, stats(ExactStatus::Optimal, 0, cost, cost)));
+        }
+        Err(err) => return Err(ExactError::Solver(err.to_string())),
+    };
+
+    let decisions = formulation.num_decisions();
+    let binaries = formulation.binaries().collect::<Vec<_>>();
+    let mut best: (Option<Vec<f64>>, usize) = (None, cost);
+    let mut stack = vec![Node {
+        bound: root.objective(),
+        parent: root,
+        fixing: None,
+    }];
+    let mut nodes = 0;
+    let mut status = ExactStatus::Optimal;
+
+    while let Some(node) = stack.pop() {
+        if params
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            status = ExactStatus::TimeLimit;
+        } else if params.node_limit.is_some_and(|limit| nodes >= limit) {
+            status = ExactStatus::NodeLimit;
+        }
+        if status != ExactStatus::Optimal {
+            stack.push(node);
+            break;
+        }
+        if !improves(node.bound, best.1) {
+            continue;
+        }
+
+        nodes += 1;
+        let solution = match node.fixing {
+            None => node.parent,
+            Some((var, value)) => match node.parent.fix_var(variables[var], value) {
+                Ok(solution) => solution,
+                Err(Error::Infeasible) => continue,
+                Err(err) => return Err(ExactError::Solver(err.to_string())),
+            },
+        };
+        let objective = solution.objective();
+        if !improves(objective, best.1) {
+            continue;
+        }
+
+        let fraction = |var: &usize| {
+            let value = solution[variables[*var]];
+            (value - value.round()).abs()
+        };
+        let most_fractional = |vars: &[usize]| {
+            vars.iter()
+                .filter(|var| fraction(var) > EPSILON)
+                .max_by(|a, b| fraction(a).total_cmp(&fraction(b)))
+                .copied()
+        };
+        let (decision_vars, other_vars) = binaries.split_at(decisions);
+
+        match most_fractional(decision_vars).or_else(|| most_fractional(other_vars)) {
+            Some(var) => {
+                let rounded = solution[variables[var]].round();
+                stack.push(Node {
+                    parent: solution.clone(),
+                    fixing: Some((var, 1.0 - rounded)),
+                    bound: objective,
+                });
+                stack.push(Node {
+                    parent: solution,
+                    fixing: Some((var, rounded)),
+                    bound: objective,
+                });
+            }
+            None => {
+                let values = variables.iter().map(|var| solution[*var]).collect();
+                best = (Some(values), (objective - EPSILON).ceil() as usize);
+            }
+        }
+    }
+
+    let bound = stack
+        .iter()
+        .map(|node| (node.bound - EPSILON).ceil().max(0.0) as usize)
+        .min()
+        .map_or(best.1, |bound| bound.min(best.1));
+    Ok((best.0, stats(status, nodes, best.1, bound)))
+}
+
+/// Whether a relaxation with this objective may contain a solution cheaper than `cost`
+fn improves(objective: f64, cost: usize) -> bool {
+    ((objective - EPSILON).ceil() as usize) < cost
+}
+
+fn stats(status: ExactStatus, nodes: usize, cost: usize, bound: usize) -> ExactStats {
+    ExactStats {
+        status,
+        nodes,
+        cost,
+        bound,
+    }
+}
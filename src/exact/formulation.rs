@@ -0,0 +1,528 @@
+//! Mixed integer linear program of batching and waving, which can be written as LP or MPS file
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use microlp::{ComparisonOp, OptimizationDirection, Problem};
+
+use super::ExactError;
+use crate::bounds::LowerBound;
+use crate::cost::TourModel;
+use crate::ga::batches::WaiveMapping;
+use crate::ga::orders::BatchMapping;
+use crate::model::*;
+
+/// Max number of terms per line of an LP file, which keeps the lines well below 255 characters
+const TERMS_PER_LINE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Sense {
+    Le,
+    Eq,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+struct Variable {
+    name: String,
+    cost: f64,
+    /// Upper bound of a continuous variable, `None` for a binary one
+    upper: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Constraint {
+    name: String,
+    pub(crate) terms: Vec<(usize, f64)>,
+    pub(crate) sense: Sense,
+    pub(crate) rhs: f64,
+}
+
+/// The batching and waving of a model as a mixed integer linear program
+///
+/// Binary variables assign every article to a batch (`x`), every batch to a waive (`z`) and mark
+/// the used batches (`y`) and waives (`u`) and the warehouses (`h`) and aisles (`g`) that every
+/// batch visits. Continuous variables (`n`) count the articles that a batch adds to a waive.
+/// Constraints keep every batch within the max weight and every waive within the max articles.
+/// The objective is the overall cost of the counting tour model.
+///
+/// Cuts demand at least as many batches, waives and visits of every warehouse and aisle as the
+/// [`LowerBound`] of the model, which the relaxation alone falls far short of.
+///
+/// The number of batches and waives is limited to the given numbers of slots. Batches and waives
+/// are ordered by their smallest article and batch, which removes symmetric solutions.
+#[derive(Debug, Clone)]
+pub struct Formulation {
+    variables: Vec<Variable>,
+    constraints: Vec<Constraint>,
+    /// Variable of every article and batch, by article index; batches above the index are left out
+    x: Vec<Vec<usize>>,
+    /// Variable of every batch and waive, by batch; waives above the batch are left out
+    z: Vec<Vec<usize>>,
+    /// Number of leading binary variables whose values decide a solution, `x` and `z`
+    num_decisions: usize,
+}
+
+impl Formulation {
+    /// Formulates the model with room for the given numbers of batches and waives
+    ///
+    /// Fails for cost models that the formulation does not cover: the distance tour model and
+    /// penalties for split orders.
+    pub fn new(
+        model: &Model,
+        num_batches: usize,
+        num_waives: usize,
+    ) -> Result<Formulation, ExactError> {
+        let cost_model = model.cost_model();
+        if cost_model.tour_model == TourModel::Distance {
+            return Err(ExactError::Unsupported("the distance tour model"));
+        }
+        if cost_model.penalty_per_batch_split > 0 || cost_model.penalty_per_waive_split > 0 {
+            return Err(ExactError::Unsupported("penalties for split orders"));
+        }
+
+        let articles = model.get_ordered_articles();
+        let num_batches = num_batches.min(articles.len()).max(1);
+        let num_waives = num_waives.min(num_batches).max(1);
+        let mut formulation = Formulation {
+            variables: Vec::new(),
+            constraints: Vec::new(),
+            x: Vec::new(),
+            z: Vec::new(),
+            num_decisions: 0,
+        };
+
+        let x = (0..articles.len())
+            .map(|a| {
+                (0..num_batches.min(a + 1))
+                    .map(|b| formulation.binary(format!("x_a{}_b{}", a, b), 0))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let z = (0..num_batches)
+            .map(|b| {
+                (0..num_waives.min(b + 1))
+                    .map(|k| formulation.binary(format!("z_b{}_k{}", b, k), 0))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        formulation.num_decisions = formulation.variables.len();
+
+        // both the tour cost and the rest cost of a batch charge the cost per batch
+        let y = (0..num_batches)
+            .map(|b| formulation.binary(format!("y_b{}", b), 2 * cost_model.cost_per_batch))
+            .collect::<Vec<_>>();
+        let u = (0..num_waives)
+            .map(|k| formulation.binary(format!("u_k{}", k), cost_model.cost_per_waive))
+            .collect::<Vec<_>>();
+
+        let mut aisles: BTreeMap<(ID, ID), Vec<usize>> = BTreeMap::new();
+        for (a, article) in articles.iter().enumerate() {
+            let location = (article.location.warehouse, article.location.aisle);
+            aisles.entry(location).or_default().push(a);
+        }
+        let mut warehouses: BTreeMap<ID, Vec<usize>> = BTreeMap::new();
+        // visit variables of every aisle and warehouse, over all batches
+        let mut aisle_visits: BTreeMap<(ID, ID), Vec<usize>> = BTreeMap::new();
+        let mut warehouse_visits: BTreeMap<ID, Vec<usize>> = BTreeMap::new();
+
+        // visited aisles and warehouses
+        for b in 0..num_batches {
+            for ((warehouse, aisle), aisle_articles) in aisles.iter() {
+                let in_batch = aisle_articles
+                    .iter()
+                    .filter(|a| b < x[**a].len())
+                    .collect::<Vec<_>>();
+                if in_batch.is_empty() {
+                    continue;
+                }
+
+                let g = formulation.binary(
+                    format!("g_w{}_a{}_b{}", warehouse, aisle, b),
+                    cost_model.cost_per_aisle,
+                );
+                for a in in_batch {
+                    formulation.constrain(
+                        format!("aisle_a{}_b{}", a, b),
+                        vec![(x[*a][b], 1.0), (g, -1.0)],
+                        Sense::Le,
+                        0.0,
+                    );
+                }
+                warehouses.entry(*warehouse).or_default().push(g);
+                aisle_visits.entry((*warehouse, *aisle)).or_default().push(g);
+            }
+
+            for (warehouse, aisles) in std::mem::take(&mut warehouses) {
+                let h = formulation.binary(
+                    format!("h_w{}_b{}", warehouse, b),
+                    cost_model.cost_per_warehouse,
+                );
+                for g in aisles {
+                    let name = formulation.variables[g].name.clone();
+                    formulation.constrain(
+                        format!("warehouse_{}", name),
+                        vec![(g, 1.0), (h, -1.0)],
+                        Sense::Le,
+                        0.0,
+                    );
+                }
+                // only used batches visit warehouses, which tightens the relaxation
+                formulation.constrain(
+                    format!("used_w{}_b{}", warehouse, b),
+                    vec![(h, 1.0), (y[b], -1.0)],
+                    Sense::Le,
+                    0.0,
+                );
+                warehouse_visits.entry(warehouse).or_default().push(h);
+            }
+        }
+
+        let lower_bound = LowerBound::new(model);
+        for ((warehouse, aisle), visits) in aisle_visits {
+            formulation.constrain(
+                format!("visits_w{}_a{}", warehouse, aisle),
+                visits.iter().map(|var| (*var, 1.0)).collect(),
+                Sense::Ge,
+                lower_bound.aisle_visits[&(warehouse, aisle)] as f64,
+            );
+        }
+        for (warehouse, visits) in warehouse_visits {
+            formulation.constrain(
+                format!("visits_w{}", warehouse),
+                visits.iter().map(|var| (*var, 1.0)).collect(),
+                Sense::Ge,
+                lower_bound.warehouse_visits[&warehouse] as f64,
+            );
+        }
+        formulation.constrain(
+            "batches".to_owned(),
+            y.iter().map(|var| (*var, 1.0)).collect(),
+            Sense::Ge,
+            lower_bound.num_batches as f64,
+        );
+        formulation.constrain(
+            "waives".to_owned(),
+            u.iter().map(|var| (*var, 1.0)).collect(),
+            Sense::Ge,
+            lower_bound.num_waives as f64,
+        );
+
+        for (a, batches) in x.iter().enumerate() {
+            formulation.constrain(
+                format!("assign_a{}", a),
+                batches.iter().map(|var| (*var, 1.0)).collect(),
+                Sense::Eq,
+                1.0,
+            );
+        }
+
+        let max_weight = cost_model.max_weight_per_batch as f64;
+        // more articles than fit into a batch by their volumes cannot be in a batch
+        let max_items = model.max_items_per_batch().max(1) as f64;
+        // article counts that every batch adds to a waive, by waive
+        let mut counts = vec![Vec::new(); num_waives];
+        for b in 0..num_batches {
+            let batch_articles = (b..articles.len())
+                .map(|a| (a, x[a][b]))
+                .collect::<Vec<_>>();
+
+            let mut terms = batch_articles
+                .iter()
+                .map(|(a, var)| (*var, articles[*a].volume as f64))
+                .collect::<Vec<_>>();
+            terms.push((y[b], -max_weight));
+            formulation.constrain(format!("volume_b{}", b), terms, Sense::Le, 0.0);
+
+            let mut terms = z[b].iter().map(|var| (*var, 1.0)).collect::<Vec<_>>();
+            terms.push((y[b], -1.0));
+            formulation.constrain(format!("waive_b{}", b), terms, Sense::Eq, 0.0);
+
+            if b + 1 < num_batches {
+                formulation.constrain(
+                    format!("order_b{}", b),
+                    vec![(y[b + 1], 1.0), (y[b], -1.0)],
+                    Sense::Le,
+                    0.0,
+                );
+            }
+
+            // n >= articles of the batch, if the batch is in the waive
+            for (k, z_var) in z[b].iter().enumerate() {
+                let n = formulation.continuous(format!("n_b{}_k{}", b, k), max_items);
+                counts[k].push(n);
+                let mut terms = batch_articles
+                    .iter()
+                    .map(|(_, var)| (*var, -1.0))
+                    .collect::<Vec<_>>();
+                terms.push((n, 1.0));
+                terms.push((*z_var, -max_items));
+                formulation.constrain(format!("count_b{}_k{}", b, k), terms, Sense::Ge, -max_items);
+                formulation.constrain(
+                    format!("used_b{}_k{}", b, k),
+                    vec![(*z_var, 1.0), (u[k], -1.0)],
+                    Sense::Le,
+                    0.0,
+                );
+            }
+        }
+
+        let max_articles = cost_model.max_articles_per_waive as f64;
+        for (k, u_var) in u.iter().enumerate() {
+            let mut terms = counts[k].iter().map(|n| (*n, 1.0)).collect::<Vec<_>>();
+            terms.push((*u_var, -max_articles));
+            formulation.constrain(format!("size_k{}", k), terms, Sense::Le, 0.0);
+
+            if k + 1 < num_waives {
+                formulation.constrain(
+                    format!("order_k{}", k),
+                    vec![(u[k + 1], 1.0), (*u_var, -1.0)],
+                    Sense::Le,
+                    0.0,
+                );
+            }
+        }
+
+        formulation.x = x;
+        formulation.z = z;
+        Ok(formulation)
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn num_constraints(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Writes the program in the CPLEX LP format
+    pub fn write_lp(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "\\ Order batching: batches and waives of minimal cost")?;
+        writeln!(out, "Minimize")?;
+        let objective = self
+            .variables
+            .iter()
+            .enumerate()
+            .filter(|(_, var)| var.cost != 0.0)
+            .map(|(idx, var)| (idx, var.cost))
+            .collect::<Vec<_>>();
+        self.write_lp_expression(out, " obj:", &objective)?;
+        writeln!(out)?;
+
+        writeln!(out, "Subject To")?;
+        for constraint in self.constraints.iter() {
+            self.write_lp_expression(out, &format!(" {}:", constraint.name), &constraint.terms)?;
+            let sense = match constraint.sense {
+                Sense::Le => "<=",
+                Sense::Eq => "=",
+                Sense::Ge => ">=",
+            };
+            writeln!(out, " {} {}", sense, constraint.rhs)?;
+        }
+
+        writeln!(out, "Bounds")?;
+        for var in self.variables.iter() {
+            if let Some(upper) = var.upper {
+                writeln!(out, " 0 <= {} <= {}", var.name, upper)?;
+            }
+        }
+
+        writeln!(out, "Binaries")?;
+        for var in self.variables.iter().filter(|var| var.upper.is_none()) {
+            writeln!(out, " {}", var.name)?;
+        }
+        writeln!(out, "End")
+    }
+
+    fn write_lp_expression(
+        &self,
+        out: &mut impl Write,
+        label: &str,
+        terms: &[(usize, f64)],
+    ) -> io::Result<()> {
+        write!(out, "{}", label)?;
+        for (idx, (var, coefficient)) in terms.iter().enumerate() {
+            if idx > 0 && idx % TERMS_PER_LINE == 0 {
+                write!(out, "\n   ")?;
+            }
+            let sign = if *coefficient < 0.0 { '-' } else { '+' };
+            write!(
+                out,
+                " {} {} {}",
+                sign,
+                coefficient.abs(),
+                self.variables[*var].name
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the program in the free MPS format
+    pub fn write_mps(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "NAME order_batching")?;
+        writeln!(out, "ROWS")?;
+        writeln!(out, " N obj")?;
+        for constraint in self.constraints.iter() {
+            let sense = match constraint.sense {
+                Sense::Le => 'L',
+                Sense::Eq => 'E',
+                Sense::Ge => 'G',
+            };
+            writeln!(out, " {} {}", sense, constraint.name)?;
+        }
+
+        let mut columns: Vec<Vec<(usize, f64)>> = vec![Vec::new(); self.variables.len()];
+        for (row, constraint) in self.constraints.iter().enumerate() {
+            for (var, coefficient) in constraint.terms.iter() {
+                columns[*var].push((row, *coefficient));
+            }
+        }
+
+        writeln!(out, "COLUMNS")?;
+        writeln!(out, " MARKER 'MARKER' 'INTORG'")?;
+        for (var, column) in self.variables.iter().zip(columns.iter()) {
+            if var.upper.is_some() {
+                continue;
+            }
+            self.write_mps_column(out, var, column)?;
+        }
+        writeln!(out, " MARKER 'MARKER' 'INTEND'")?;
+        for (var, column) in self.variables.iter().zip(columns.iter()) {
+            if var.upper.is_none() {
+                continue;
+            }
+            self.write_mps_column(out, var, column)?;
+        }
+
+        writeln!(out, "RHS")?;
+        for constraint in self.constraints.iter().filter(|c| c.rhs != 0.0) {
+            writeln!(out, " rhs {} {}", constraint.name, constraint.rhs)?;
+        }
+
+        writeln!(out, "BOUNDS")?;
+        for var in self.variables.iter() {
+            match var.upper {
+                Some(upper) => writeln!(out, " UP bnd {} {}", var.name, upper)?,
+                None => writeln!(out, " BV bnd {}", var.name)?,
+            }
+        }
+        writeln!(out, "ENDATA")
+    }
+
+    fn write_mps_column(
+        &self,
+        out: &mut impl Write,
+        var: &Variable,
+        column: &[(usize, f64)],
+    ) -> io::Result<()> {
+        if var.cost != 0.0 {
+            writeln!(out, " {} obj {}", var.name, var.cost)?;
+        }
+        for (row, coefficient) in column.iter() {
+            writeln!(
+                out,
+                " {} {} {}",
+                var.name, self.constraints[*row].name, coefficient
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The linear relaxation of the program, every binary variable between 0 and 1
+    pub(crate) fn relaxation(&self) -> (Problem, Vec<microlp::Variable>) {
+        let mut problem = Problem::new(OptimizationDirection::Minimize);
+        let variables = self
+            .variables
+            .iter()
+            .map(|var| problem.add_var(var.cost, (0.0, var.upper.unwrap_or(1.0))))
+            .collect::<Vec<_>>();
+
+        for constraint in self.constraints.iter() {
+            let op = match constraint.sense {
+                Sense::Le => ComparisonOp::Le,
+                Sense::Eq => ComparisonOp::Eq,
+                Sense::Ge => ComparisonOp::Ge,
+            };
+            problem.add_constraint(
+                constraint
+                    .terms
+                    .iter()
+                    .map(|(var, coefficient)| (variables[*var], *coefficient)),
+                op,
+                constraint.rhs,
+            );
+        }
+        (problem, variables)
+    }
+
+    /// Indices of the binary variables, those that decide a solution first
+    pub(crate) fn binaries(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.variables.len()).filter(|idx| self.variables[*idx].upper.is_none())
+    }
+
+    /// Number of leading binary variables whose values decide a solution
+    pub(crate) fn num_decisions(&self) -> usize {
+        self.num_decisions
+    }
+
+    /// Batch mapping and waive mapping of integral variable values
+    ///
+    /// Batches and waives are numbered in the order of their slots, leaving out the empty ones.
+    pub(crate) fn decode(&self, values: &[f64]) -> (BatchMapping, WaiveMapping) {
+        let slot_of = |vars: &[usize]| {
+            vars.iter()
+                .position(|var| values[*var] > 0.5)
+                .expect("every article in a batch, every used batch in a waive")
+        };
+
+        let batch_slots = self.x.iter().map(|vars| slot_of(vars)).collect::<Vec<_>>();
+        let mut used_batches = batch_slots.clone();
+        used_batches.sort_unstable();
+        used_batches.dedup();
+        let batch_mapping = batch_slots
+            .iter()
+            .map(|slot| used_batches.binary_search(slot).expect("used batch"))
+            .collect();
+
+        let waive_slots = used_batches
+            .iter()
+            .map(|b| slot_of(&self.z[*b]))
+            .collect::<Vec<_>>();
+        let mut used_waives = waive_slots.clone();
+        used_waives.sort_unstable();
+        used_waives.dedup();
+        let waive_mapping = waive_slots
+            .iter()
+            .map(|slot| used_waives.binary_search(slot).expect("used waive"))
+            .collect();
+
+        (batch_mapping, waive_mapping)
+    }
+
+    fn binary(&mut self, name: String, cost: usize) -> usize {
+        self.variables.push(Variable {
+            name,
+            cost: cost as f64,
+            upper: None,
+        });
+        self.variables.len() - 1
+    }
+
+    fn continuous(&mut self, name: String, upper: f64) -> usize {
+        self.variables.push(Variable {
+            name,
+            cost: 0.0,
+            upper: Some(upper),
+        });
+        self.variables.len() - 1
+    }
+
+    fn constrain(&mut self, name: String, terms: Vec<(usize, f64)>, sense: Sense, rhs: f64) {
+        self.constraints.push(Constraint {
+            name,
+            terms,
+            sense,
+            rhs,
+        });
+    }
+}
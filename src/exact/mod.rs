@@ -0,0 +1,165 @@
+//! Branch and bound over a mixed integer program, which proves optimality for tiny instances
+use std::fmt;
+use std::time::Instant;
+
+use crate::cost::CostModel;
+use crate::ga::batches::{WaiveMapping, WaivedBatches};
+use crate::ga::orders::BatchedArticles;
+use crate::model::*;
+
+mod branch_and_bound;
+pub mod formulation;
+
+pub use formulation::Formulation;
+
+/// Why the branch and bound cannot solve a model
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExactError {
+    /// The formulation does not cover this part of the cost model
+    Unsupported(&'static str),
+    /// The linear program solver failed
+    Solver(String),
+}
+
+impl fmt::Display for ExactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExactError::Unsupported(what) => {
+                write!(f, "The branch and bound does not support {}", what)
+            }
+            ExactError::Solver(message) => write!(f, "The LP solver failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ExactError {}
+
+/// Parameters of the branch and bound
+#[derive(Debug, Clone, Copy)]
+pub struct ExactParams {
+    /// Max number of linear programs to solve, unlimited if `None`
+    pub node_limit: Option<usize>,
+    /// Point in time at which the search stops and returns the best solution found so far
+    pub deadline: Option<Instant>,
+}
+
+impl Default for ExactParams {
+    fn default() -> Self {
+        ExactParams {
+            node_limit: Some(1000),
+            deadline: None,
+        }
+    }
+}
+
+/// Why the branch and bound stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExactStatus {
+    /// The found solution is optimal
+    Optimal,
+    /// The node limit stopped the search
+    NodeLimit,
+    /// The time limit stopped the search
+    TimeLimit,
+}
+
+/// Statistics of a finished branch and bound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExactStats {
+    pub status: ExactStatus,
+    /// Number of solved linear programs
+    pub nodes: usize,
+    /// Cost of the best solution found
+    pub cost: usize,
+    /// Lower bound of the cost of any solution, equal to `cost` if the solution is optimal
+    pub bound: usize,
+}
+
+impl ExactStats {
+    /// Gap between cost and bound, relative to the cost
+    pub fn gap(&self) -> f64 {
+        if self.cost == 0 {
+            0.0
+        } else {
+            (self.cost - self.bound) as f64 / self.cost as f64
+        }
+    }
+}
+
+impl fmt::Display for ExactStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cost {}, bound {} (gap {:.1}%, {} nodes)",
+            self.cost,
+            self.bound,
+            self.gap() * 100.0,
+            self.nodes
+        )?;
+        match self.status {
+            ExactStatus::Optimal => write!(f, ", optimal"),
+            ExactStatus::NodeLimit => write!(f, ", stopped by node limit"),
+            ExactStatus::TimeLimit => write!(f, ", stopped by time limit"),
+        }
+    }
+}
+
+/// Formulates the model with as many batches and waives as a solution may use that is not more
+/// expensive than the given one
+pub fn formulate(
+    model: &Model,
+    batched_articles: &BatchedArticles,
+    waive_mapping: &WaiveMapping,
+) -> Result<Formulation, ExactError> {
+    let cost_model = model.cost_model();
+    let cost = overall_cost(cost_model, batched_articles, waive_mapping);
+    let min_batch_cost =
+        2 * cost_model.cost_per_batch + cost_model.cost_per_warehouse + cost_model.cost_per_aisle;
+
+    let num_articles = model.get_ordered_articles().len();
+    let num_batches = (cost - cost.min(cost_model.cost_per_waive))
+        .checked_div(min_batch_cost)
+        .unwrap_or(num_articles);
+    let num_waives = (cost - cost.min(model.min_batches_num() * min_batch_cost))
+        .checked_div(cost_model.cost_per_waive)
+        .unwrap_or(num_batches);
+
+    Formulation::new(model, num_batches, num_waives)
+}
+
+/// Searches the cheapest batches and waives by branch and bound, starting from a known solution
+///
+/// Only supports the counting tour model without penalties for split orders. The given solution
+/// sizes the formulation, see [`formulate`], and prunes the search; it is returned if the search
+/// finds nothing cheaper.
+pub fn solve_exact<'a>(
+    model: &'a Model,
+    batched_articles: &BatchedArticles<'a>,
+    waive_mapping: &WaiveMapping,
+    params: ExactParams,
+) -> Result<(BatchedArticles<'a>, WaiveMapping, ExactStats), ExactError> {
+    let formulation = formulate(model, batched_articles, waive_mapping)?;
+    let cost = overall_cost(model.cost_model(), batched_articles, waive_mapping);
+    let (values, stats) = branch_and_bound::solve(&formulation, cost, params)?;
+
+    Ok(match values {
+        Some(values) => {
+            let (batch_mapping, waive_mapping) = formulation.decode(&values);
+            let batched_articles = BatchedArticles::from_batch_mapping(batch_mapping, model);
+            (batched_articles, waive_mapping, stats)
+        }
+        None => (batched_articles.clone(), waive_mapping.clone(), stats),
+    })
+}
+
+fn overall_cost(
+    cost_model: &CostModel,
+    batched_articles: &BatchedArticles,
+    waive_mapping: &WaiveMapping,
+) -> usize {
+    let waived_batches = WaivedBatches::from_waive_mapping(waive_mapping.clone(), batched_articles);
+    batched_articles
+        .cost(cost_model)
+        .expect("solution within the max weight")
+        + waived_batches.rest_cost(cost_model)
+}
@@ -2,18 +2,24 @@
 use std::collections::BTreeSet;
 use std::ops::Div;
 
+use anyhow::Result;
+
+use crate::cost::CostModel;
 use crate::ga::orders::{Batch, BatchedArticles};
+use crate::ga::evolution::{evolve, Fitness, GenomeConfig};
+use crate::ga::{GaParams, Infeasible, SearchStats};
+use crate::heuristics::repair::{
+    keep_orders_in_waives, repair_oversized_waives, InseparableOrders,
+};
 use crate::model::*;
 
-use genevo::{operator::prelude::*, population::ValueEncodedGenomeBuilder, prelude::*};
-
-type Fitness = usize;
+use genevo::prelude::*;
 
 /// A mapping from batches to waives
 ///
 /// Acts as genotype / individual
 #[derive(Clone, Debug)]
-pub(crate) struct WaivedBatches<'a> {
+pub struct WaivedBatches<'a> {
     waive_mapping: WaiveMapping,
     waives: Vec<Waive<'a>>,
 }
@@ -31,26 +37,22 @@ impl<'a> PartialOrd for WaivedBatches<'a> {
 }
 
 impl<'a> WaivedBatches<'a> {
-    fn from_waive_mapping(
+    /// Builds the waives, `waive_mapping` maps the batches of `batched_articles` by index
+    pub fn from_waive_mapping(
         waive_mapping: WaiveMapping,
         batched_articles: &'a BatchedArticles,
     ) -> WaivedBatches<'a> {
-        let mut waives: Vec<Waive<'a>> = (0..batched_articles.len())
-            .into_iter()
-            .map(|_| Waive::new())
-            .collect();
+        let mut waives: Vec<Waive<'a>> =
+            (0..batched_articles.len()).map(|_| Waive::new()).collect();
 
         let batches = batched_articles.to_batches();
 
         batches.iter().enumerate().for_each(|(idx, batch)| {
             let waive_id = waive_mapping[idx];
-            waives[waive_id as usize].push(batch.to_owned())
+            waives[waive_id].push(batch.to_owned())
         });
 
-        waives = waives
-            .into_iter()
-            .filter(|waive| waive.num_batches() > 0)
-            .collect();
+        waives.retain(|waive| waive.num_batches() > 0);
 
         WaivedBatches {
             waive_mapping,
@@ -58,7 +60,11 @@ impl<'a> WaivedBatches<'a> {
         }
     }
 
-    pub(crate) fn to_waives(&self) -> &Vec<Waive<'a>> {
+    pub fn waive_mapping(&self) -> &WaiveMapping {
+        &self.waive_mapping
+    }
+
+    pub fn to_waives(&self) -> &Vec<Waive<'a>> {
         &self.waives
         // let mut waives: Vec<Waive<'a>> = (0..batched_articles.len())
         //     .into_iter()
@@ -82,11 +88,11 @@ impl<'a> WaivedBatches<'a> {
     }
 
     #[allow(dead_code)]
-    pub(crate) fn has_split_orders(&self) -> bool {
-        self.get_split_orders().len() > 0
+    pub fn has_split_orders(&self) -> bool {
+        !self.get_split_orders().is_empty()
     }
 
-    pub(crate) fn get_split_orders(&self) -> BTreeSet<ID> {
+    pub fn get_split_orders(&self) -> BTreeSet<ID> {
         let order_ids_per_batch = self
             .to_waives()
             .iter()
@@ -111,10 +117,34 @@ impl<'a> WaivedBatches<'a> {
         split_order_ids
     }
 
-    pub(crate) fn rest_cost(&self) -> usize {
+    pub fn rest_cost(&self, cost_model: &CostModel) -> usize {
         let num_waives = self.waive_mapping.iter().collect::<BTreeSet<_>>().len();
 
-        num_waives * COST_PER_WAIVE
+        num_waives * cost_model.cost_per_waive
+    }
+
+    /// Penalty for the orders that are picked in more than one waive
+    pub fn split_cost(&self, cost_model: &CostModel) -> usize {
+        self.get_split_orders().len() * cost_model.penalty_per_waive_split
+    }
+
+    /// Rest cost plus the penalties for split orders, including the one that only guides the search
+    pub(crate) fn penalized_cost(&self, cost_model: &CostModel) -> usize {
+        self.rest_cost(cost_model)
+            + self.get_split_orders().len()
+                * (cost_model.penalty_per_split_order + cost_model.penalty_per_waive_split)
+    }
+
+    /// Number of articles by which the waives exceed the max articles, summed up
+    pub(crate) fn excess_articles(&self, cost_model: &CostModel) -> usize {
+        self.waives
+            .iter()
+            .map(|waive| {
+                waive
+                    .num_articles()
+                    .saturating_sub(cost_model.max_articles_per_waive)
+            })
+            .sum()
     }
 }
 
@@ -124,18 +154,18 @@ impl<'a> Genotype for WaivedBatches<'a> {
 
 /// A singe batch, containing (ordered) articles
 #[derive(Clone, Debug)]
-pub(crate) struct Waive<'a> {
+pub struct Waive<'a> {
     batches: Vec<Batch<'a>>,
 }
 
 impl<'a> Waive<'a> {
-    fn new() -> Waive<'a> {
+    pub(crate) fn new() -> Waive<'a> {
         Waive {
             batches: Vec::new(),
         }
     }
 
-    fn push(&mut self, batch: Batch<'a>) {
+    pub(crate) fn push(&mut self, batch: Batch<'a>) {
         self.batches.push(batch);
     }
 
@@ -143,15 +173,15 @@ impl<'a> Waive<'a> {
         self.batches.len()
     }
 
-    pub(crate) fn batches(&self) -> &Vec<Batch<'a>> {
+    pub fn batches(&self) -> &Vec<Batch<'a>> {
         &self.batches
     }
 
-    pub(crate) fn num_articles(&self) -> usize {
+    pub fn num_articles(&self) -> usize {
         self.batches.iter().map(Batch::num_articles).sum::<usize>()
     }
 
-    pub(crate) fn order_ids_in_waive(&self) -> BTreeSet<ID> {
+    pub fn order_ids_in_waive(&self) -> BTreeSet<ID> {
         self.batches
             .iter()
             .flat_map(|batch| batch.order_ids_in_batch().into_iter())
@@ -160,17 +190,20 @@ impl<'a> Waive<'a> {
 }
 
 /// id (index) of a single, specific waive
-type WaiveId = usize;
+pub type WaiveId = usize;
 
 /// A 'mapping' from batches (by index) to waives (by id / index)
 ///
 /// Acts as DNA for the genotype `WaivedBatches`
-type WaiveMapping = Vec<WaiveId>;
+pub type WaiveMapping = Vec<WaiveId>;
 
 #[derive(Debug, Clone, Copy)]
 struct FitnessCalc<'a> {
     model: &'a Model,
     batched_articles: &'a BatchedArticles<'a>,
+    /// Whether waivings get repaired to keep every order in a single waive
+    strict: bool,
+    infeasible: Infeasible,
 }
 
 impl<'a> FitnessCalc<'a> {
@@ -183,55 +216,81 @@ impl<'a> FitnessCalc<'a> {
             .sum::<usize>()
             .div(batches.len());
 
-        let average_batches_per_waive = MAX_ARTICLES_PER_WAIVE / average_articles_per_batch;
+        let average_batches_per_waive =
+            self.model.cost_model().max_articles_per_waive / average_articles_per_batch;
 
         (batches.len() as f32) / (average_batches_per_waive as f32).min(1f32)
     }
+
+    /// Lower bound for the cost of any waiving
+    fn min_cost(&self) -> usize {
+        self.model.min_waives_num() * self.model.cost_model().cost_per_waive
+    }
+
+    /// Cost of the worst valid waiving, which puts every batch into a waive of its own and splits
+    /// every order, plus the highest possible penalty for oversized waives
+    fn max_cost(&self) -> usize {
+        let cost_model = self.model.cost_model();
+        let mut max_cost = self.batched_articles.to_batches().len() * cost_model.cost_per_waive
+            + self.model.num_orders()
+                * (cost_model.penalty_per_split_order + cost_model.penalty_per_waive_split);
+        if self.infeasible == Infeasible::Penalize {
+            // no waive exceeds the max articles by more than the number of all articles
+            max_cost += self.model.max_batches_num() * cost_model.penalty_per_excess_article;
+        }
+        max_cost
+    }
+
+    /// Repairs the waiving to keep orders together if strict, and then to keep every waive within
+    /// the max articles unless it gets rejected or penalized
+    fn repaired(&self, waive_mapping: &WaiveMapping) -> Result<WaiveMapping, InseparableOrders> {
+        let waive_mapping = if self.strict {
+            keep_orders_in_waives(self.model, self.batched_articles, waive_mapping)?
+        } else {
+            waive_mapping.to_owned()
+        };
+
+        Ok(match self.infeasible {
+            Infeasible::Repair => {
+                repair_oversized_waives(self.model, self.batched_articles, &waive_mapping)
+            }
+            Infeasible::Reject | Infeasible::Penalize => waive_mapping,
+        })
+    }
 }
 
 impl<'a> FitnessFunction<WaiveMapping, Fitness> for FitnessCalc<'a> {
+    /// Ranks waivings by their rest cost plus the penalties for split orders, the cheaper the
+    /// fitter
+    ///
+    /// Valid waivings get a fitness of at least 1, rejected ones get 0. Waivings are ranked after
+    /// their repair, see [`FitnessCalc::repaired`].
     fn fitness_of(&self, waive_mapping: &WaiveMapping) -> Fitness {
+        let cost_model = self.model.cost_model();
+        let waive_mapping = match self.repaired(waive_mapping) {
+            Ok(waive_mapping) => waive_mapping,
+            Err(_) => return 0,
+        };
         let waived_batches =
-            WaivedBatches::from_waive_mapping(waive_mapping.to_owned(), self.batched_articles);
-        let waives = waived_batches.to_waives();
+            WaivedBatches::from_waive_mapping(waive_mapping, self.batched_articles);
 
-        let has_invalid_waive = waives
-            .iter()
-            .any(|wave| wave.num_articles() > MAX_ARTICLES_PER_WAIVE);
-
-        if has_invalid_waive {
+        let excess_articles = waived_batches.excess_articles(cost_model);
+        if excess_articles > 0 && self.infeasible != Infeasible::Penalize {
             return 0;
         }
 
-        let base_fitness = {
-            // let average_num_of_waves = self.average_num_of_waives(self.model);
-            // let relative_fitness = average_num_of_waves / (waives.len().max(1) as f32);
-            let relative_fitness = 1f32 / (waives.len().max(1) as f32);
-            (relative_fitness * 100f32) as Fitness
-        };
-
-        let num_split_orders = waived_batches.get_split_orders().len();
-
-        let max_num_split_orders = self.model.num_orders();
-
-        let split_bonus = max_num_split_orders - num_split_orders;
+        let cost = waived_batches.penalized_cost(cost_model)
+            + excess_articles * cost_model.penalty_per_excess_article;
 
-        // add split bonus if base fitness is still low to favour solution that dont have any
-        // penalty for splitting order
-        if base_fitness < split_bonus {
-            (base_fitness + split_bonus).min(100)
-        } else {
-            base_fitness.min(100)
-        }
+        (self.max_cost() + 1).saturating_sub(cost)
     }
 
     fn average(&self, a: &[Fitness]) -> Fitness {
-        // todo add penalty for used number of waives
         a.iter().sum::<Fitness>() / a.len()
     }
 
     fn highest_possible_fitness(&self) -> Fitness {
-        100
+        self.max_cost() + 1 - self.min_cost()
     }
 
     fn lowest_possible_fitness(&self) -> Fitness {
@@ -239,21 +298,23 @@ impl<'a> FitnessFunction<WaiveMapping, Fitness> for FitnessCalc<'a> {
     }
 }
 
-struct GenomeConfig {
-    length: usize,
-    min_value: usize,
-    max_value: usize,
-}
-
-pub(crate) fn find_best_waives<'a>(
+/// Searches the best waives, starting from a random population that contains the given seeds
+///
+/// If `strict`, every waiving gets repaired by [`keep_orders_in_waives`], including the result.
+/// Unless `params` reject them, waives that exceed the max articles get repaired by
+/// [`repair_oversized_waives`].
+pub fn find_best_waives<'a>(
     model: &'a Model,
     batched_articles: &'a BatchedArticles,
-    num_individuals: usize,
-    num_generations: usize,
-) -> WaivedBatches<'a> {
+    params: GaParams,
+    strict: bool,
+    seeds: &[WaiveMapping],
+) -> Result<(WaivedBatches<'a>, SearchStats)> {
     let fitness_calc = FitnessCalc {
         model,
         batched_articles,
+        strict,
+        infeasible: params.infeasible,
     };
 
     let genome_config = GenomeConfig {
@@ -262,68 +323,16 @@ pub(crate) fn find_best_waives<'a>(
         max_value: batched_articles.to_batches().len() - 1,
     };
 
-    let initial_population: Population<_> = build_population()
-        .with_genome_builder(ValueEncodedGenomeBuilder::new(
-            genome_config.length,
-            genome_config.min_value,
-            genome_config.max_value,
-        ))
-        .of_size(num_individuals)
-        .uniform_at_random();
-
-    let mut batch_sim = simulate(
-        genetic_algorithm()
-            .with_evaluation(fitness_calc)
-            .with_selection(RouletteWheelSelector::new(0.7, 2))
-            .with_crossover(UniformCrossBreeder::new())
-            .with_mutation(RandomValueMutator::new(
-                0.05,
-                genome_config.min_value,
-                genome_config.max_value,
-            ))
-            .with_reinsertion(ElitistReinserter::new(fitness_calc, true, 0.7))
-            .with_initial_population(initial_population)
-            .build(),
-    )
-    .until(Or::new(
-        GenerationLimit::new(num_generations as u64),
-        FitnessLimit::new(100),
+    let (waive_mapping, stats) = evolve(fitness_calc, genome_config, &params, seeds)?;
+    let waive_mapping = fitness_calc.repaired(&waive_mapping)?;
+    // a penalized waiving only gets repaired once it is the best one
+    let waive_mapping = match params.infeasible {
+        Infeasible::Penalize => repair_oversized_waives(model, batched_articles, &waive_mapping),
+        Infeasible::Reject | Infeasible::Repair => waive_mapping,
+    };
+
+    Ok((
+        WaivedBatches::from_waive_mapping(waive_mapping, batched_articles),
+        stats,
     ))
-    .build();
-
-    loop {
-        match batch_sim.step() {
-            Ok(SimResult::Intermediate(step)) => {
-                if cfg!(feature = "verbose") {
-                    if cfg!(feature = "verbose") {
-                        println!(
-                            "Generation {} fitness {}",
-                            step.result.best_solution.generation,
-                            step.result.best_solution.solution.fitness
-                        );
-                    }
-                }
-            }
-            Ok(SimResult::Final(step, time, duration, stop_reason)) => {
-                if cfg!(feature = "info") {
-                    println!(
-                        "Generation {} fitness {}",
-                        step.result.best_solution.generation,
-                        step.result.best_solution.solution.fitness
-                    );
-                }
-                if cfg!(feature = "info") {
-                    println!(
-                        "Time: {} Duration {} Stop reason {}",
-                        time, duration, stop_reason
-                    );
-                }
-                let batch_mapping = step.result.best_solution.solution.genome;
-                return WaivedBatches::from_waive_mapping(batch_mapping, batched_articles);
-            }
-            Err(err) => {
-                panic!("{}", err)
-            }
-        }
-    }
 }
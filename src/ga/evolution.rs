@@ -0,0 +1,286 @@
+//! Runs the genetic algorithm shared by all searches, on one population or on several islands
+use std::fmt::Debug;
+use std::thread;
+
+use anyhow::{bail, Result};
+use genevo::{
+    operator::prelude::*,
+    population::ValueEncodedGenomeBuilder,
+    prelude::*,
+    random::{Prng, Rng, Seed, SeedableRng},
+};
+
+use crate::ga::termination::StopCriteria;
+use crate::ga::{with_seeds, GaParams, IslandStats, SearchStats, StopReason, MIN_POPULATION_SIZE};
+
+/// Number of best individuals that move to the next island at each migration
+const NUM_MIGRANTS: usize = 2;
+
+/// All searches encode their solutions as a vector of ids
+pub(crate) type Genome = Vec<usize>;
+
+/// Fitness of all searches, the higher the better
+pub(crate) type Fitness = usize;
+
+/// Length and value range of a genome
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GenomeConfig {
+    pub(crate) length: usize,
+    pub(crate) min_value: usize,
+    pub(crate) max_value: usize,
+}
+
+/// Evolves random populations that contain the given seeds and returns the best genome found
+///
+/// Runs a single population, unless `params.islands` asks for several. Fails if a population is
+/// smaller than [`MIN_POPULATION_SIZE`].
+pub(crate) fn evolve<E>(
+    fitness_calc: E,
+    genome_config: GenomeConfig,
+    params: &GaParams,
+    seeds: &[Genome],
+) -> Result<(Genome, SearchStats)>
+where
+    E: FitnessFunction<Genome, Fitness> + Debug + Send + Sync,
+{
+    let (population_seed, simulation_seed) = params.seeds();
+    let population = with_seeds(
+        random_population(genome_config, params, population_seed),
+        seeds,
+    );
+
+    let islands = match params.islands {
+        Some(islands) if islands.islands > 1 => islands,
+        _ => {
+            let criteria = StopCriteria::new(params, fitness_calc.highest_possible_fitness());
+            let epoch = run_epoch(
+                &fitness_calc,
+                genome_config,
+                population,
+                criteria,
+                simulation_seed,
+            )?;
+            let stats = SearchStats {
+                generations: epoch.generations,
+                stop_reason: epoch.stop_reason,
+                islands: Vec::new(),
+            };
+            return Ok((epoch.best_genome, stats));
+        }
+    };
+
+    // every island gets its own random decisions, only the first one starts with the seeds
+    let mut rng = Prng::from_seed(simulation_seed);
+    let mut populations = vec![population];
+    let mut island_rngs = vec![Prng::from_seed(rng.gen())];
+    for _ in 1..islands.islands {
+        populations.push(random_population(genome_config, params, rng.gen()));
+        island_rngs.push(Prng::from_seed(rng.gen()));
+    }
+    let mut stats = vec![IslandStats::default(); islands.islands];
+
+    let mut criteria = StopCriteria::new(params, fitness_calc.highest_possible_fitness());
+    let mut generations = 0;
+    let mut best: Option<(Genome, Fitness)> = None;
+    loop {
+        let epoch_params = GaParams {
+            num_generations: islands
+                .migration_interval
+                .min(params.num_generations - generations as usize),
+            stagnation: None,
+            islands: None,
+            ..*params
+        };
+
+        let epochs = thread::scope(|scope| {
+            let handles = populations
+                .drain(..)
+                .zip(island_rngs.iter_mut())
+                .map(|(population, rng)| {
+                    let criteria =
+                        StopCriteria::new(&epoch_params, fitness_calc.highest_possible_fitness());
+                    let seed = rng.gen();
+                    let fitness_calc = &fitness_calc;
+                    scope.spawn(move || {
+                        run_epoch(fitness_calc, genome_config, population, criteria, seed)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("island panicked"))
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let epoch_generations = epochs
+            .iter()
+            .map(|epoch| epoch.generations)
+            .max()
+            .unwrap_or(0);
+        generations += epoch_generations;
+        for (epoch, stats) in epochs.iter().zip(stats.iter_mut()) {
+            stats.generations += epoch.generations;
+            stats.best_fitness = stats.best_fitness.max(epoch.best_fitness);
+            if best
+                .as_ref()
+                .is_none_or(|(_, fitness)| epoch.best_fitness > *fitness)
+            {
+                best = Some((epoch.best_genome.clone(), epoch.best_fitness));
+            }
+        }
+
+        let best_fitness = best.as_ref().map_or(0, |(_, fitness)| *fitness);
+        let fitness_reached = epochs
+            .iter()
+            .any(|epoch| epoch.stop_reason == StopReason::FitnessLimit);
+        let stop_reason = if fitness_reached {
+            Some(StopReason::FitnessLimit)
+        } else {
+            criteria.check(generations, epoch_generations as usize, &best_fitness)
+        };
+        if let Some(stop_reason) = stop_reason {
+            let (best_genome, _) = best.expect("islands ran at least one generation");
+            let stats = SearchStats {
+                generations,
+                stop_reason,
+                islands: stats,
+            };
+            return Ok((best_genome, stats));
+        }
+
+        populations = migrate(epochs, &mut stats);
+    }
+}
+
+/// The state of a population after some generations
+struct Epoch {
+    individuals: Vec<Genome>,
+    fitness_values: Vec<Fitness>,
+    best_genome: Genome,
+    best_fitness: Fitness,
+    generations: u64,
+    stop_reason: StopReason,
+}
+
+fn random_population(
+    genome_config: GenomeConfig,
+    params: &GaParams,
+    seed: Seed,
+) -> Population<Genome> {
+    build_population()
+        .with_genome_builder(ValueEncodedGenomeBuilder::new(
+            genome_config.length,
+            genome_config.min_value,
+            genome_config.max_value,
+        ))
+        .of_size(params.num_individuals)
+        .using_seed(seed)
+}
+
+/// Runs the genetic algorithm on a population until one of the criteria stops it
+fn run_epoch<E>(
+    fitness_calc: &E,
+    genome_config: GenomeConfig,
+    population: Population<Genome>,
+    criteria: StopCriteria<Fitness>,
+    seed: Seed,
+) -> Result<Epoch>
+where
+    E: FitnessFunction<Genome, Fitness> + Debug + Sync,
+{
+    let mut sim = simulate(
+        genetic_algorithm()
+            .with_evaluation(fitness_calc.clone())
+            // tournaments only compare fitness values, so the selection pressure does not depend
+            // on the offset between fitness and cost
+            .with_selection(TournamentSelector::new(0.7, 2, 3, 1.0, false))
+            .with_crossover(UniformCrossBreeder::new())
+            .with_mutation(RandomValueMutator::new(
+                0.05,
+                genome_config.min_value,
+                genome_config.max_value,
+            ))
+            .with_reinsertion(ElitistReinserter::new(fitness_calc.clone(), true, 0.7))
+            .with_initial_population(population)
+            .with_min_population_size(MIN_POPULATION_SIZE)
+            .build(),
+    )
+    .until(criteria)
+    .build_with_seed(seed);
+
+    loop {
+        match sim.step() {
+            Ok(SimResult::Intermediate(step)) => {
+                if cfg!(feature = "verbose") {
+                    println!(
+                        "Generation {} fitness {}",
+                        step.result.best_solution.generation,
+                        step.result.best_solution.solution.fitness
+                    );
+                }
+            }
+            Ok(SimResult::Final(step, time, duration, stop_reason)) => {
+                if cfg!(feature = "info") {
+                    println!(
+                        "Generation {} fitness {}",
+                        step.result.best_solution.generation,
+                        step.result.best_solution.solution.fitness
+                    );
+                    println!(
+                        "Time: {} Duration {} Stop reason {}",
+                        time, duration, stop_reason
+                    );
+                }
+                let population = step.result.evaluated_population;
+                return Ok(Epoch {
+                    individuals: population.individuals().to_vec(),
+                    fitness_values: population.fitness_values().to_vec(),
+                    best_genome: step.result.best_solution.solution.genome,
+                    best_fitness: step.result.best_solution.solution.fitness,
+                    generations: step.iteration,
+                    stop_reason: sim
+                        .termination()
+                        .stop_reason()
+                        .expect("simulation stopped without reason"),
+                });
+            }
+            Err(err) => bail!("{}", err),
+        }
+    }
+}
+
+/// Replaces the worst individuals of every island by the best ones of the previous island (ring)
+fn migrate(epochs: Vec<Epoch>, stats: &mut [IslandStats]) -> Vec<Population<Genome>> {
+    let migrants = epochs
+        .iter()
+        .map(|epoch| {
+            ranked(epoch)
+                .into_iter()
+                .take(NUM_MIGRANTS)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    epochs
+        .iter()
+        .enumerate()
+        .map(|(idx, epoch)| {
+            let from = (idx + epochs.len() - 1) % epochs.len();
+            let mut individuals = epoch.individuals.clone();
+            let worst = ranked(epoch).into_iter().rev();
+            for (slot, migrant) in worst.zip(migrants[from].iter()) {
+                individuals[slot] = epochs[from].individuals[*migrant].clone();
+                stats[idx].migrants += 1;
+            }
+            Population::with_individuals(individuals)
+        })
+        .collect()
+}
+
+/// Indices of the individuals of an epoch, from the fittest to the least fit
+fn ranked(epoch: &Epoch) -> Vec<usize> {
+    let mut indices = (0..epoch.individuals.len()).collect::<Vec<_>>();
+    indices.sort_by_key(|idx| std::cmp::Reverse(epoch.fitness_values[*idx]));
+    indices
+}
@@ -0,0 +1,206 @@
+//! Genetic search for partitioning orders into batches and batches into waives at once
+use anyhow::Result;
+
+use crate::ga::batches::{WaiveMapping, WaivedBatches};
+use crate::ga::evolution::{evolve, Fitness, GenomeConfig};
+use crate::ga::orders::{
+    self, to_batch_mapping, to_genes, ArticleGroup, BatchEncoding, BatchedArticles,
+};
+use crate::ga::{GaParams, Infeasible, SearchStats};
+use crate::heuristics::repair::{keep_orders_in_waives, repair_oversized_waives};
+use crate::model::*;
+
+use genevo::prelude::*;
+
+/// A mapping from the genes of the batch encoding to batches, followed by a mapping from batches
+/// (by id) to waives
+///
+/// Both parts have one gene per group of articles of the encoding, as there are at most that many
+/// batches. Acts as genotype / individual of the joint search.
+pub type JointMapping = Vec<usize>;
+
+/// Combines a batching and a waiving of its batches into one genome
+///
+/// The batching must keep the articles of every group in one batch, with batch ids below the
+/// number of groups, as the greedy batches of the same encoding do.
+pub fn joint_mapping(
+    groups: &[ArticleGroup],
+    batched_articles: &BatchedArticles,
+    waive_mapping: &[usize],
+) -> JointMapping {
+    let mut waive_genes = vec![0; groups.len()];
+    batched_articles
+        .to_batches()
+        .iter()
+        .zip(waive_mapping)
+        .for_each(|(batch, &waive_id)| waive_genes[batch.id] = waive_id);
+
+    let mut mapping = to_genes(groups, batched_articles.batch_mapping());
+    mapping.extend(waive_genes);
+    mapping
+}
+
+/// Splits a genome into its batching and the waive mapping of the batching's batches
+fn split<'a>(
+    joint_mapping: &JointMapping,
+    groups: &[ArticleGroup],
+    model: &'a Model,
+) -> (BatchedArticles<'a>, WaiveMapping) {
+    let (batch_genes, waive_genes) = joint_mapping.split_at(groups.len());
+    let batched_articles =
+        BatchedArticles::from_batch_mapping(to_batch_mapping(groups, batch_genes), model);
+    let waive_mapping = batched_articles
+        .to_batches()
+        .iter()
+        .map(|batch| waive_genes[batch.id])
+        .collect();
+
+    (batched_articles, waive_mapping)
+}
+
+#[derive(Debug, Clone)]
+struct FitnessCalc<'a> {
+    model: &'a Model,
+    batches: orders::FitnessCalc<'a>,
+    /// Whether waivings get repaired to keep every order in a single waive
+    strict: bool,
+    infeasible: Infeasible,
+}
+
+impl<'a> FitnessCalc<'a> {
+    /// Lower bound for the overall cost of any solution
+    fn min_cost(&self) -> usize {
+        self.batches.min_cost()
+            + self.model.min_waives_num() * self.model.cost_model().cost_per_waive
+    }
+
+    /// Cost of the worst valid solution, which puts every article into a batch and a waive of its
+    /// own and splits every order, plus the highest possible penalties for overflows
+    fn max_cost(&self) -> usize {
+        let cost_model = self.model.cost_model();
+        let mut max_cost = self.batches.max_cost()
+            + self.model.max_batches_num() * cost_model.cost_per_waive
+            + self.model.num_orders()
+                * (cost_model.penalty_per_split_order + cost_model.penalty_per_waive_split);
+        if self.infeasible == Infeasible::Penalize {
+            max_cost += self.model.max_batches_num() * cost_model.penalty_per_excess_article;
+        }
+        max_cost
+    }
+
+    /// Moves articles out of overweight batches and then batches out of oversized waives
+    fn repaired(&self, genes: &JointMapping) -> JointMapping {
+        let groups = self.batches.groups();
+        let (batch_genes, waive_genes) = genes.split_at(groups.len());
+        // new batches take the waive genes of their ids
+        let mut repaired = self.batches.repaired(&batch_genes.to_vec());
+        repaired.extend_from_slice(waive_genes);
+
+        let (batched_articles, waive_mapping) = split(&repaired, groups, self.model);
+        let waive_mapping = repair_oversized_waives(self.model, &batched_articles, &waive_mapping);
+        joint_mapping(groups, &batched_articles, &waive_mapping)
+    }
+}
+
+impl<'a> FitnessFunction<JointMapping, Fitness> for FitnessCalc<'a> {
+    /// Ranks solutions by their overall cost plus the soft penalty for orders split across waives,
+    /// the cheaper the fitter
+    ///
+    /// Valid solutions get a fitness of at least 1, rejected ones get 0. Overflows get repaired or
+    /// penalized, as given by `infeasible`. If strict, solutions are ranked after the repair of
+    /// their waives.
+    fn fitness_of(&self, joint_mapping: &JointMapping) -> Fitness {
+        let cost_model = self.model.cost_model();
+        let joint_mapping = match self.infeasible {
+            Infeasible::Repair => self.repaired(joint_mapping),
+            Infeasible::Reject | Infeasible::Penalize => joint_mapping.clone(),
+        };
+        let groups = self.batches.groups();
+        let (batch_genes, _) = joint_mapping.split_at(groups.len());
+        let batch_cost = match self.batches.cost_of(&batch_genes.to_vec()) {
+            Some(batch_cost) => batch_cost,
+            None => return 0,
+        };
+
+        let (batched_articles, waive_mapping) = split(&joint_mapping, groups, self.model);
+        let waive_mapping = if self.strict {
+            match keep_orders_in_waives(self.model, &batched_articles, &waive_mapping) {
+                Ok(waive_mapping) => waive_mapping,
+                Err(_) => return 0,
+            }
+        } else {
+            waive_mapping
+        };
+
+        let waived_batches = WaivedBatches::from_waive_mapping(waive_mapping, &batched_articles);
+        let excess_articles = waived_batches.excess_articles(cost_model);
+        if excess_articles > 0 && self.infeasible != Infeasible::Penalize {
+            return 0;
+        }
+
+        let cost = batch_cost
+            + waived_batches.penalized_cost(cost_model)
+            + excess_articles * cost_model.penalty_per_excess_article;
+
+        (self.max_cost() + 1).saturating_sub(cost)
+    }
+
+    fn average(&self, a: &[Fitness]) -> Fitness {
+        a.iter().sum::<Fitness>() / a.len()
+    }
+
+    fn highest_possible_fitness(&self) -> Fitness {
+        self.max_cost() + 1 - self.min_cost()
+    }
+
+    fn lowest_possible_fitness(&self) -> Fitness {
+        0
+    }
+}
+
+/// Searches the best batches and waives together, starting from a random population that contains
+/// the given seeds
+///
+/// The batch part of the genomes has a gene per group of articles of the encoding, see
+/// [`joint_mapping`] for the seeds.
+///
+/// Returns the batches and the mapping of their batches (by index) to waives, see
+/// [`WaivedBatches::from_waive_mapping`]. If `strict`, the waives of every solution get repaired by
+/// [`keep_orders_in_waives`], including the result. Unless `params` reject them, overflows of the
+/// result get repaired.
+pub fn find_best_joint<'a>(
+    model: &'a Model,
+    params: GaParams,
+    encoding: BatchEncoding,
+    strict: bool,
+    seeds: &[JointMapping],
+) -> Result<(BatchedArticles<'a>, WaiveMapping, SearchStats)> {
+    let fitness_calc = FitnessCalc {
+        model,
+        batches: orders::FitnessCalc::new(model, encoding, params.infeasible),
+        strict,
+        infeasible: params.infeasible,
+    };
+    let groups = fitness_calc.batches.groups().to_vec();
+
+    // there are never more batches than genes, and batch ids and waive ids share the same range
+    let genome_config = GenomeConfig {
+        length: 2 * groups.len(),
+        min_value: 0,
+        max_value: groups.len() - 1,
+    };
+
+    let (joint_mapping, stats) = evolve(fitness_calc.clone(), genome_config, &params, seeds)?;
+    let joint_mapping = match params.infeasible {
+        Infeasible::Reject => joint_mapping,
+        Infeasible::Repair | Infeasible::Penalize => fitness_calc.repaired(&joint_mapping),
+    };
+    let (batched_articles, waive_mapping) = split(&joint_mapping, &groups, model);
+    let waive_mapping = if strict {
+        keep_orders_in_waives(model, &batched_articles, &waive_mapping)?
+    } else {
+        waive_mapping
+    };
+
+    Ok((batched_articles, waive_mapping, stats))
+}
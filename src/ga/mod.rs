@@ -1,3 +1,144 @@
 //! Contains functions for genetic algorithm search
-pub(crate) mod batches;
-pub(crate) mod orders;
+use std::fmt;
+use std::time::Instant;
+
+use genevo::prelude::{Genotype, Population};
+use genevo::random::{Prng, Rng, Seed, SeedableRng};
+
+pub mod batches;
+mod evolution;
+pub mod joint;
+pub mod orders;
+mod termination;
+
+/// Smallest population a genetic search runs with
+pub const MIN_POPULATION_SIZE: usize = 6;
+
+/// Parameters of a single genetic search
+#[derive(Debug, Clone, Copy)]
+pub struct GaParams {
+    /// Initial size of the population, at least [`MIN_POPULATION_SIZE`]
+    pub num_individuals: usize,
+    /// Max number of generations
+    pub num_generations: usize,
+    /// Seed for all random decisions of the search
+    pub seed: u64,
+    /// Point in time at which the search stops and returns the best solution found so far
+    pub deadline: Option<Instant>,
+    /// Stops the search early if the best fitness does not improve anymore
+    pub stagnation: Option<Stagnation>,
+    /// Runs several populations in parallel instead of a single one
+    pub islands: Option<Islands>,
+    /// How the search ranks individuals that exceed a max weight or max articles
+    pub infeasible: Infeasible,
+}
+
+impl GaParams {
+    /// Derives the seeds for building the initial population and for running the simulation
+    fn seeds(&self) -> (Seed, Seed) {
+        let mut rng = Prng::seed_from_u64(self.seed);
+        (rng.gen(), rng.gen())
+    }
+}
+
+/// Replaces the first individuals of a population by the given seeds
+fn with_seeds<G: Genotype>(population: Population<G>, seeds: &[G]) -> Population<G> {
+    let mut individuals = population.individuals().to_vec();
+    individuals
+        .iter_mut()
+        .zip(seeds)
+        .for_each(|(individual, seed)| *individual = seed.clone());
+
+    Population::with_individuals(individuals)
+}
+
+/// How a search deals with individuals whose batches exceed the max weight or whose waives exceed
+/// the max articles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Infeasible {
+    /// Ranks them lowest, with a fitness of 0
+    Reject,
+    /// Moves the overflowing articles or batches into batches or waives with enough room, or into
+    /// new ones, before ranking them
+    #[default]
+    Repair,
+    /// Ranks them by their cost plus a penalty per unit of overflow, see
+    /// [`crate::CostModel::penalty_per_overweight`]; the result gets repaired
+    Penalize,
+}
+
+/// Stop criterion for a search whose best fitness does not improve anymore
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stagnation {
+    /// Number of generations without improvement after which the search stops
+    pub generations: usize,
+    /// Min improvement relative to the best fitness so far, smaller ones do not count
+    pub epsilon: f64,
+}
+
+/// Island model: independent populations on separate threads that exchange their best individuals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Islands {
+    /// Number of populations, each running on its own thread
+    pub islands: usize,
+    /// Number of generations between two migrations
+    pub migration_interval: usize,
+}
+
+/// The criterion that stopped a genetic search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    GenerationLimit,
+    FitnessLimit,
+    TimeLimit,
+    Stagnation,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StopReason::GenerationLimit => write!(f, "generation limit"),
+            StopReason::FitnessLimit => write!(f, "fitness limit"),
+            StopReason::TimeLimit => write!(f, "time limit"),
+            StopReason::Stagnation => write!(f, "stagnation"),
+        }
+    }
+}
+
+/// Statistics of a finished genetic search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchStats {
+    pub generations: u64,
+    pub stop_reason: StopReason,
+    /// Statistics per island, empty if the search ran a single population
+    pub islands: Vec<IslandStats>,
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} generations, stopped by {}",
+            self.generations, self.stop_reason
+        )
+    }
+}
+
+/// Statistics of a single island of a finished genetic search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IslandStats {
+    pub generations: u64,
+    pub best_fitness: usize,
+    /// Number of individuals received from the neighboring island
+    pub migrants: usize,
+}
+
+impl fmt::Display for IslandStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} generations, best fitness {}, {} migrants",
+            self.generations, self.best_fitness, self.migrants
+        )
+    }
+}
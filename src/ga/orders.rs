@@ -1,17 +1,23 @@
 //! Genetic search for partitioning order into batches
 use std::collections::BTreeSet;
 
+use anyhow::Result;
+
+use crate::batching::Evaluator;
+use crate::bounds::LowerBound;
+use crate::cost::CostModel;
+use crate::ga::evolution::{evolve, Fitness, Genome, GenomeConfig};
+use crate::ga::{GaParams, Infeasible, SearchStats};
+use crate::heuristics::repair::repair_overflow;
 use crate::model::*;
 
-use genevo::{operator::prelude::*, population::ValueEncodedGenomeBuilder, prelude::*};
-
-type Fitness = usize;
+use genevo::prelude::*;
 
 /// A set of batches
 ///
 /// Acts as genotype / individual
 #[derive(Clone, Debug)]
-pub(crate) struct BatchedArticles<'a> {
+pub struct BatchedArticles<'a> {
     batch_mapping: BatchMapping,
     batches: Vec<Batch<'a>>,
 }
@@ -29,9 +35,11 @@ impl<'a> PartialOrd for BatchedArticles<'a> {
 }
 
 impl<'a> BatchedArticles<'a> {
-    fn from_batch_mapping(batch_mapping: BatchMapping, model: &'a Model) -> BatchedArticles<'a> {
+    pub fn from_batch_mapping(
+        batch_mapping: BatchMapping,
+        model: &'a Model,
+    ) -> BatchedArticles<'a> {
         let mut batches: Vec<Batch> = (0..model.max_batches_num())
-            .into_iter()
             .enumerate()
             .map(|(idx, _)| Batch::new(idx))
             .collect();
@@ -42,13 +50,10 @@ impl<'a> BatchedArticles<'a> {
             .enumerate()
             .for_each(|(idx, article)| {
                 let batch_id = batch_mapping[idx];
-                batches[batch_id as usize].push(article)
+                batches[batch_id].push(article)
             });
 
-        batches = batches
-            .into_iter()
-            .filter(|batch| batch.num_articles() > 0)
-            .collect();
+        batches.retain(|batch| batch.num_articles() > 0);
 
         BatchedArticles {
             batch_mapping,
@@ -56,26 +61,62 @@ impl<'a> BatchedArticles<'a> {
         }
     }
 
+    pub fn batch_mapping(&self) -> &BatchMapping {
+        &self.batch_mapping
+    }
+
     pub(crate) fn len(&self) -> usize {
         self.batch_mapping.len()
     }
 
-    pub(crate) fn to_batches(&self) -> &Vec<Batch<'a>> {
+    pub fn to_batches(&self) -> &Vec<Batch<'a>> {
         &self.batches
     }
 
-    pub(crate) fn rest_cost(&self) -> usize {
+    pub fn rest_cost(&self, cost_model: &CostModel) -> usize {
         let num_batches = self.batch_mapping.iter().collect::<BTreeSet<_>>().len();
 
-        num_batches * COST_PER_BATCH
+        num_batches * cost_model.cost_per_batch
+    }
+
+    /// Tour cost plus rest cost, or `None` if any batch exceeds the max weight
+    pub fn cost(&self, cost_model: &CostModel) -> Option<usize> {
+        self.tour_cost(cost_model)
+            .map(|tour_cost| tour_cost + self.rest_cost(cost_model))
     }
 
-    pub(crate) fn tour_cost(&self) -> Option<usize> {
+    pub fn tour_cost(&self, cost_model: &CostModel) -> Option<usize> {
         self.to_batches()
             .iter()
-            .map(Batch::fitness)
+            .map(|batch| batch.fitness(cost_model))
             .sum::<Option<usize>>()
     }
+
+    /// Ids of the orders whose articles are picked in more than one batch
+    pub fn get_split_orders(&self) -> BTreeSet<ID> {
+        split_orders(self.to_batches())
+    }
+
+    /// Penalty for the orders whose articles are picked in more than one batch
+    pub fn split_cost(&self, cost_model: &CostModel) -> usize {
+        self.get_split_orders().len() * cost_model.penalty_per_batch_split
+    }
+}
+
+/// Ids of the orders whose articles are picked in more than one of the given batches
+pub fn split_orders<'a, 'b: 'a>(batches: impl IntoIterator<Item = &'a Batch<'b>>) -> BTreeSet<ID> {
+    let mut seen_order_ids = BTreeSet::new();
+    let mut split_order_ids = BTreeSet::new();
+
+    for batch in batches {
+        for order_id in batch.order_ids_in_batch() {
+            if !seen_order_ids.insert(order_id) {
+                split_order_ids.insert(order_id);
+            }
+        }
+    }
+
+    split_order_ids
 }
 
 impl<'a> Genotype for BatchedArticles<'a> {
@@ -84,110 +125,238 @@ impl<'a> Genotype for BatchedArticles<'a> {
 
 /// A singe batch, containing (ordered) articles
 #[derive(Debug, Clone)]
-pub(crate) struct Batch<'a> {
-    pub(crate) id: BatchId,
+pub struct Batch<'a> {
+    pub id: BatchId,
     ordered_articles: Vec<&'a OrderedArticle>,
 }
 
 impl<'a> Batch<'a> {
-    fn new(id: BatchId) -> Batch<'a> {
+    pub(crate) fn new(id: BatchId) -> Batch<'a> {
         Batch {
             id,
             ordered_articles: Vec::new(),
         }
     }
 
-    fn push(&mut self, article: &'a OrderedArticle) {
+    pub(crate) fn push(&mut self, article: &'a OrderedArticle) {
         self.ordered_articles.push(article);
     }
 
-    pub(crate) fn fitness(&self) -> Option<usize> {
-        if self.volume() > MAX_WEIGHT_PER_BATCH {
+    pub fn fitness(&self, cost_model: &CostModel) -> Option<usize> {
+        if self.volume() > cost_model.max_weight_per_batch {
             None
         } else {
-            Some(
-                self.num_warehouses() * COST_PER_WAREHOUSE
-                    + self.num_aisles() * COST_PER_AISLE
-                    + COST_PER_BATCH,
-            )
+            Some(self.tour_cost(cost_model))
         }
     }
 
-    pub(crate) fn ordered_articles(&self) -> &Vec<&OrderedArticle> {
+    /// Cost of the batch's tour, regardless of whether the batch exceeds the max weight
+    pub fn tour_cost(&self, cost_model: &CostModel) -> usize {
+        let locations = self.ordered_articles.iter().map(|article| &article.location);
+        cost_model.picking_cost(locations) + cost_model.cost_per_batch
+    }
+
+    pub fn ordered_articles(&self) -> &Vec<&OrderedArticle> {
         &self.ordered_articles
     }
 
-    pub(crate) fn num_articles(&self) -> usize {
+    pub fn num_articles(&self) -> usize {
         self.ordered_articles.len()
     }
 
-    pub(crate) fn volume(&self) -> u16 {
+    pub fn volume(&self) -> Volume {
         self.ordered_articles
             .iter()
-            .map(|article| article.volume as u16)
-            .sum::<u16>()
+            .map(|article| article.volume)
+            .sum::<Volume>()
     }
 
-    fn num_warehouses(&self) -> usize {
+    pub fn order_ids_in_batch(&self) -> BTreeSet<ID> {
         self.ordered_articles
             .iter()
-            .map(|article| article.location.warehouse)
-            .collect::<BTreeSet<_>>()
-            .len()
+            .map(|article| article.order_id)
+            .collect()
     }
+}
 
-    fn num_aisles(&self) -> usize {
-        self.ordered_articles
-            .iter()
-            //TODO: check if two aisles w/ same ID in different warehouses count as one or two
-            .map(|article| (article.location.warehouse, article.location.aisle))
-            .collect::<BTreeSet<_>>()
-            .len()
+/// id (index) of a single, specific batch
+pub type BatchId = usize;
+
+/// What a gene of the orders<->batches search stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BatchEncoding {
+    /// A gene per ordered article, orders may be split over several batches
+    #[default]
+    Articles,
+    /// A gene per order, which keeps every order in a single batch
+    ///
+    /// The articles of an order that exceeds the max weight of a batch get a gene each instead.
+    Orders,
+}
+
+/// Articles (by index) that share a gene and therefore always end up in the same batch
+pub type ArticleGroup = Vec<usize>;
+
+/// Groups the ordered articles of the model by the genes of an encoding
+pub fn article_groups(model: &Model, encoding: BatchEncoding) -> Vec<ArticleGroup> {
+    let articles = model.get_ordered_articles();
+    let max_weight = model.cost_model().max_weight_per_batch;
+
+    match encoding {
+        BatchEncoding::Articles => (0..articles.len()).map(|idx| vec![idx]).collect(),
+        BatchEncoding::Orders => {
+            let mut groups = Vec::new();
+            // the articles of every order are next to each other
+            let indices = (0..articles.len()).collect::<Vec<_>>();
+            for order in indices.chunk_by(|a, b| articles[*a].order_id == articles[*b].order_id) {
+                let volume = order
+                    .iter()
+                    .map(|idx| articles[*idx].volume)
+                    .sum::<Volume>();
+                if volume <= max_weight {
+                    groups.push(order.to_vec());
+                } else {
+                    groups.extend(order.iter().map(|idx| vec![*idx]));
+                }
+            }
+            groups
+        }
     }
+}
 
-    pub(crate) fn order_ids_in_batch(&self) -> BTreeSet<ID> {
-        self.ordered_articles
-            .iter()
-            .map(|article| article.order_id)
-            .collect()
+/// Assigns every article the batch of its group, as given by the genes (one per group)
+pub(crate) fn to_batch_mapping(groups: &[ArticleGroup], genes: &[BatchId]) -> BatchMapping {
+    let mut batch_mapping = vec![0; groups.iter().map(Vec::len).sum()];
+    for (group, batch_id) in groups.iter().zip(genes) {
+        group.iter().for_each(|idx| batch_mapping[*idx] = *batch_id);
     }
+    batch_mapping
 }
 
-/// id (index) of a single, specific batch
-type BatchId = usize;
+/// Batch of every group, by the batch of its first article
+pub(crate) fn to_genes(groups: &[ArticleGroup], batch_mapping: &[BatchId]) -> Genome {
+    groups.iter().map(|group| batch_mapping[group[0]]).collect()
+}
 
 /// A 'mapping' from articles (by index) to batches (by id / index)
 ///
 /// Acts as DNA for the genotype `BatchedArticles`
-type BatchMapping = Vec<BatchId>;
+pub type BatchMapping = Vec<BatchId>;
 
-#[derive(Debug, Clone, Copy)]
-struct FitnessCalc<'a> {
+#[derive(Debug, Clone)]
+pub(crate) struct FitnessCalc<'a> {
     model: &'a Model,
+    evaluator: Evaluator<'a>,
+    /// Articles of every gene
+    groups: Vec<ArticleGroup>,
+    /// Volume of the articles of every gene
+    group_volumes: Vec<Volume>,
+    infeasible: Infeasible,
+    min_cost: usize,
+    max_cost: usize,
 }
 
 impl<'a> FitnessCalc<'a> {
-    fn best_batch_fitness_approx(&self) -> usize {
-        self.model.num_warehouses_of_orders() * 10 + self.model.num_aisles_of_orders() * 5
-    }
-}
+    pub(crate) fn new(
+        model: &'a Model,
+        encoding: BatchEncoding,
+        infeasible: Infeasible,
+    ) -> FitnessCalc<'a> {
+        let cost_model = model.cost_model();
+        let articles = model.get_ordered_articles();
+        // the worst valid batching puts every article into a batch of its own and splits every
+        // order
+        let mut max_cost = articles
+            .iter()
+            .map(|article| {
+                cost_model.picking_cost([&article.location]) + 2 * cost_model.cost_per_batch
+            })
+            .sum::<usize>()
+            + model.num_orders() * cost_model.penalty_per_batch_split;
+        if infeasible == Infeasible::Penalize {
+            // no batching exceeds the max weight by more than the volume of all articles
+            max_cost += articles
+                .iter()
+                .map(|article| article.volume)
+                .sum::<Volume>()
+                * cost_model.penalty_per_overweight;
+        }
 
-impl<'a> FitnessFunction<BatchMapping, Fitness> for FitnessCalc<'a> {
-    // TODO: add penalty for used number of batches
-    // TODO: add penalty if articles of one order are in many batches
-    fn fitness_of(&self, batch_mapping: &BatchMapping) -> Fitness {
-        let batch_mapping = batch_mapping.clone();
-        let fitness = BatchedArticles::from_batch_mapping(batch_mapping, self.model)
-            .to_batches()
+        let groups = article_groups(model, encoding);
+        let group_volumes = groups
             .iter()
-            .map(Batch::fitness)
-            .sum::<Option<usize>>();
+            .map(|group| group.iter().map(|idx| articles[*idx].volume).sum())
+            .collect();
 
-        if let Some(fitness) = fitness {
-            let f = (self.best_batch_fitness_approx() as f32) * 100f32 / (fitness as f32);
-            f as Fitness
-        } else {
-            0
+        FitnessCalc {
+            model,
+            evaluator: Evaluator::new(model),
+            groups,
+            group_volumes,
+            infeasible,
+            min_cost: LowerBound::new(model).batch_cost(),
+            max_cost,
+        }
+    }
+
+    /// Articles of every gene
+    pub(crate) fn groups(&self) -> &[ArticleGroup] {
+        &self.groups
+    }
+
+    /// Moves genes out of batches that exceed the max weight, see [`repair_overflow`]
+    pub(crate) fn repaired(&self, genes: &Genome) -> Genome {
+        let mut genes = genes.clone();
+        repair_overflow(
+            &self.group_volumes,
+            &mut genes,
+            self.model.cost_model().max_weight_per_batch,
+        );
+        genes
+    }
+
+    /// Cost plus penalties of the batching of the genes, `None` if it is rejected
+    ///
+    /// Repairs or penalizes batches that exceed the max weight, as given by `infeasible`. Evaluates
+    /// the whole batch mapping in one pass: genevo hands over mutated genomes without their
+    /// parents, so the per-move updates of [`crate::batching::Batching`] only serve the local
+    /// searches.
+    pub(crate) fn cost_of(&self, genes: &Genome) -> Option<usize> {
+        let batch_mapping = match self.infeasible {
+            Infeasible::Repair => to_batch_mapping(&self.groups, &self.repaired(genes)),
+            Infeasible::Reject | Infeasible::Penalize => to_batch_mapping(&self.groups, genes),
+        };
+        let cost = match self.infeasible {
+            Infeasible::Penalize => {
+                let (cost, overweight) = self.evaluator.cost_and_overweight(&batch_mapping);
+                cost + overweight * self.model.cost_model().penalty_per_overweight
+            }
+            Infeasible::Reject | Infeasible::Repair => self.evaluator.cost(&batch_mapping)?,
+        };
+
+        Some(cost + self.evaluator.split_cost(&batch_mapping))
+    }
+
+    /// Lower bound for the cost (tour cost + rest cost) of any batching, see [`LowerBound`]
+    pub(crate) fn min_cost(&self) -> usize {
+        self.min_cost
+    }
+
+    /// Cost of the worst valid batching, which puts every article into a batch of its own and
+    /// splits every order, plus the highest possible penalty for overweight batches
+    pub(crate) fn max_cost(&self) -> usize {
+        self.max_cost
+    }
+}
+
+impl<'a> FitnessFunction<Genome, Fitness> for FitnessCalc<'a> {
+    /// Ranks batchings by their cost plus the penalties, the cheaper the fitter
+    ///
+    /// Valid batchings get a fitness of at least 1, rejected ones get 0.
+    fn fitness_of(&self, genes: &Genome) -> Fitness {
+        match self.cost_of(genes) {
+            Some(cost) => (self.max_cost() + 1).saturating_sub(cost),
+            None => 0,
         }
     }
 
@@ -196,7 +365,7 @@ impl<'a> FitnessFunction<BatchMapping, Fitness> for FitnessCalc<'a> {
     }
 
     fn highest_possible_fitness(&self) -> Fitness {
-        100
+        self.max_cost() + 1 - self.min_cost()
     }
 
     fn lowest_possible_fitness(&self) -> Fitness {
@@ -204,92 +373,44 @@ impl<'a> FitnessFunction<BatchMapping, Fitness> for FitnessCalc<'a> {
     }
 }
 
-struct GenomeConfig {
-    length: usize,
-    min_value: usize,
-    max_value: usize,
-}
-
-pub(crate) fn find_best_batches(
-    model: &Model,
-    num_individuals: usize,
-    num_generations: usize,
-) -> BatchedArticles {
-    let fitness_calc = FitnessCalc { model };
-
+/// Searches the best batches, starting from a random population that contains the given seeds
+///
+/// The seeds must keep the articles of every gene of the encoding in the same batch.
+pub fn find_best_batches<'a>(
+    model: &'a Model,
+    params: GaParams,
+    encoding: BatchEncoding,
+    seeds: &[BatchMapping],
+) -> Result<(BatchedArticles<'a>, SearchStats)> {
+    let fitness_calc = FitnessCalc::new(model, encoding, params.infeasible);
+    let groups = fitness_calc.groups.clone();
+
+    // there are never more batches than genes
     let genome_config = GenomeConfig {
-        length: model.get_ordered_articles().len(),
+        length: groups.len(),
         min_value: 0,
-        max_value: model.max_batches_num() - 1,
+        max_value: groups.len() - 1,
     };
+    let seeds = seeds
+        .iter()
+        .map(|batch_mapping| to_genes(&groups, batch_mapping))
+        .collect::<Vec<_>>();
 
     if cfg!(feature = "info") {
-        println!(
-            "Best possible tour cost: {}",
-            fitness_calc.best_batch_fitness_approx()
-        );
+        println!("Lower bound of the batch cost: {}", fitness_calc.min_cost());
     }
 
-    let initial_population: Population<_> = build_population()
-        .with_genome_builder(ValueEncodedGenomeBuilder::new(
-            genome_config.length,
-            genome_config.min_value,
-            genome_config.max_value,
-        ))
-        .of_size(num_individuals)
-        .uniform_at_random();
-
-    let mut batch_sim = simulate(
-        genetic_algorithm()
-            .with_evaluation(fitness_calc)
-            .with_selection(RouletteWheelSelector::new(0.7, 2))
-            .with_crossover(UniformCrossBreeder::new())
-            .with_mutation(RandomValueMutator::new(
-                0.05,
-                genome_config.min_value,
-                genome_config.max_value,
-            ))
-            .with_reinsertion(ElitistReinserter::new(fitness_calc, true, 0.7))
-            .with_initial_population(initial_population)
-            .build(),
-    )
-    .until(Or::new(
-        GenerationLimit::new(num_generations as u64),
-        FitnessLimit::new(100),
+    let (genes, stats) = evolve(fitness_calc.clone(), genome_config, &params, &seeds)?;
+    // the best batching may still exceed the max weight, as it was ranked after its repair or
+    // with a penalty
+    let genes = match params.infeasible {
+        Infeasible::Reject => genes,
+        Infeasible::Repair | Infeasible::Penalize => fitness_calc.repaired(&genes),
+    };
+    let batch_mapping = to_batch_mapping(&groups, &genes);
+
+    Ok((
+        BatchedArticles::from_batch_mapping(batch_mapping, model),
+        stats,
     ))
-    .build();
-
-    loop {
-        match batch_sim.step() {
-            Ok(SimResult::Intermediate(step)) => {
-                if cfg!(feature = "verbose") {
-                    println!(
-                        "Generation {} fitness {}",
-                        step.result.best_solution.generation,
-                        step.result.best_solution.solution.fitness
-                    );
-                }
-            }
-            Ok(SimResult::Final(step, time, duration, stop_reason)) => {
-                if cfg!(feature = "info") {
-                    println!(
-                        "Generation {} fitness {}",
-                        step.result.best_solution.generation,
-                        step.result.best_solution.solution.fitness
-                    );
-                }
-                if cfg!(feature = "info") {
-                    println!(
-                        "Time: {} Duration {} Stop reason {}",
-                        time, duration, stop_reason
-                    );
-                }
-                let batch_mapping = step.result.best_solution.solution.genome;
-                return BatchedArticles::from_batch_mapping(batch_mapping, model);
-            }
-            Err(err) => {
-                panic!("{}", err)
-            }
-        }
-    }
 }
@@ -0,0 +1,121 @@
+//! Custom stop criteria for genetic search
+use std::time::Instant;
+
+use genevo::{
+    ga::GeneticAlgorithm,
+    genetic::{AsScalar, Fitness, FitnessFunction, Genotype},
+    operator::{CrossoverOp, MutationOp, ReinsertionOp, SelectionOp},
+    simulation::State,
+    termination::{StopFlag, Termination},
+};
+
+use crate::ga::{GaParams, Stagnation, StopReason};
+
+/// Combines all stop criteria of a genetic search and remembers which one stopped it
+#[derive(Debug, Clone)]
+pub(crate) struct StopCriteria<F> {
+    max_generations: u64,
+    fitness_target: F,
+    deadline: Option<Instant>,
+    stagnation: Option<Stagnation>,
+    /// Best fitness at the last significant improvement
+    best_fitness: Option<f64>,
+    generations_without_improvement: usize,
+    stop_reason: Option<StopReason>,
+}
+
+impl<F> StopCriteria<F> {
+    pub(crate) fn new(params: &GaParams, fitness_target: F) -> StopCriteria<F> {
+        StopCriteria {
+            max_generations: params.num_generations as u64,
+            fitness_target,
+            deadline: params.deadline,
+            stagnation: params.stagnation,
+            best_fitness: None,
+            generations_without_improvement: 0,
+            stop_reason: None,
+        }
+    }
+
+    /// The criterion that stopped the search, if it is stopped already
+    pub(crate) fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    /// Tracks the best fitness after some more generations and returns true if it stagnates
+    fn is_stagnating(&mut self, fitness: f64, generations: usize) -> bool {
+        let stagnation = match self.stagnation {
+            Some(stagnation) => stagnation,
+            None => return false,
+        };
+
+        match self.best_fitness {
+            Some(best_fitness)
+                if fitness - best_fitness <= stagnation.epsilon * best_fitness.abs() =>
+            {
+                self.generations_without_improvement += generations;
+            }
+            _ => {
+                self.best_fitness = Some(fitness);
+                self.generations_without_improvement = 0;
+            }
+        }
+
+        self.generations_without_improvement >= stagnation.generations
+    }
+}
+
+impl<F: Fitness + AsScalar> StopCriteria<F> {
+    /// Checks all criteria after `generations` more generations, `iteration` in total
+    ///
+    /// Returns and remembers the criterion that stops the search, if any.
+    pub(crate) fn check(
+        &mut self,
+        iteration: u64,
+        generations: usize,
+        fitness: &F,
+    ) -> Option<StopReason> {
+        let stop_reason = if *fitness >= self.fitness_target {
+            Some(StopReason::FitnessLimit)
+        } else if self.is_stagnating(fitness.as_scalar(), generations) {
+            Some(StopReason::Stagnation)
+        } else if iteration >= self.max_generations {
+            Some(StopReason::GenerationLimit)
+        } else if matches!(self.deadline, Some(deadline) if Instant::now() >= deadline) {
+            Some(StopReason::TimeLimit)
+        } else {
+            None
+        };
+
+        self.stop_reason = stop_reason;
+        stop_reason
+    }
+}
+
+impl<G, F, E, S, C, M, R> Termination<GeneticAlgorithm<G, F, E, S, C, M, R>> for StopCriteria<F>
+where
+    G: Genotype,
+    F: Fitness + AsScalar + Send + Sync,
+    E: FitnessFunction<G, F> + Sync,
+    S: SelectionOp<G, F>,
+    C: CrossoverOp<G> + Sync,
+    M: MutationOp<G> + Sync,
+    R: ReinsertionOp<G, F>,
+{
+    fn evaluate(&mut self, state: &State<GeneticAlgorithm<G, F, E, S, C, M, R>>) -> StopFlag {
+        let fitness = &state.result.best_solution.solution.fitness;
+
+        match self.check(state.iteration, 1, fitness) {
+            Some(stop_reason) => {
+                StopFlag::StopNow(format!("Simulation stopped by {}.", stop_reason))
+            }
+            None => StopFlag::Continue,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.best_fitness = None;
+        self.generations_without_improvement = 0;
+        self.stop_reason = None;
+    }
+}
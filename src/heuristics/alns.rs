@@ -0,0 +1,558 @@
+//! Adaptive large neighborhood search over the batches: destroys part of a batching and repairs it
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+
+use genevo::random::{Prng, Rng, SeedableRng};
+
+use crate::batching::{Batching, Evaluator};
+use crate::ga::orders::{article_groups, ArticleGroup, BatchEncoding, BatchId, BatchedArticles};
+use crate::model::*;
+
+/// Score of an operator pair whose solution is the best so far
+const SCORE_BEST: f64 = 33.0;
+/// Score of an operator pair whose solution is cheaper than the current one
+const SCORE_BETTER: f64 = 9.0;
+/// Score of an operator pair whose solution is not cheaper but still accepted
+const SCORE_ACCEPTED: f64 = 13.0;
+
+/// Stands for an empty batch among the insertion candidates of a group
+const NEW_BATCH: BatchId = BatchId::MAX;
+
+/// Removes articles from their batches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DestroyOperator {
+    /// Removes all articles of a random aisle
+    RandomAisle,
+    /// Removes the articles of the batches with the highest cost per article
+    WorstBatches,
+    /// Removes all articles of a random order
+    RandomOrder,
+}
+
+impl DestroyOperator {
+    pub const ALL: [DestroyOperator; 3] = [
+        DestroyOperator::RandomAisle,
+        DestroyOperator::WorstBatches,
+        DestroyOperator::RandomOrder,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DestroyOperator::RandomAisle => "random aisle",
+            DestroyOperator::WorstBatches => "worst batches",
+            DestroyOperator::RandomOrder => "random order",
+        }
+    }
+}
+
+/// Inserts the removed articles into batches again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairOperator {
+    /// Inserts the article with the cheapest insertion first, into its cheapest batch
+    Greedy,
+    /// Inserts the article first that would cost the most extra if it missed its cheapest batch
+    Regret,
+}
+
+impl RepairOperator {
+    pub const ALL: [RepairOperator; 2] = [RepairOperator::Greedy, RepairOperator::Regret];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RepairOperator::Greedy => "greedy insertion",
+            RepairOperator::Regret => "regret insertion",
+        }
+    }
+}
+
+/// Parameters of a single ALNS run
+#[derive(Debug, Clone, Copy)]
+pub struct AlnsParams {
+    /// Number of destroy and repair steps
+    pub iterations: usize,
+    /// Max share of the articles (or genes) removed by a destroy step
+    pub destroy_fraction: f64,
+    /// Number of iterations between two updates of the operator weights
+    pub segment_length: usize,
+    /// How far the weights move towards the scores of the last segment, between 0 and 1
+    pub reaction: f64,
+    /// Temperature that decides whether a more expensive solution is accepted, in units of cost
+    pub start_temperature: f64,
+    /// Factor of the temperature per iteration
+    pub cooling: f64,
+    /// Seed for all random decisions of the run
+    pub seed: u64,
+    /// Point in time at which the run stops and returns the best solution found so far
+    pub deadline: Option<Instant>,
+}
+
+impl Default for AlnsParams {
+    fn default() -> Self {
+        AlnsParams {
+            iterations: 1000,
+            destroy_fraction: 0.1,
+            segment_length: 50,
+            reaction: 0.2,
+            start_temperature: 5.0,
+            cooling: 0.995,
+            seed: 0,
+            deadline: None,
+        }
+    }
+}
+
+/// How a single operator fared during a run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatorStats {
+    pub name: &'static str,
+    /// Number of iterations that used the operator
+    pub uses: usize,
+    /// Number of iterations that used the operator and found a new best solution
+    pub improvements: usize,
+    /// Number of iterations that used the operator and whose solution was accepted
+    pub accepted: usize,
+    /// Weight at the end of the run, relative to the start weight 1
+    pub weight: f64,
+}
+
+impl fmt::Display for OperatorStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} uses, {} improvements, {} accepted, weight {:.2}",
+            self.name, self.uses, self.improvements, self.accepted, self.weight
+        )
+    }
+}
+
+/// Statistics of a finished ALNS run
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlnsStats {
+    /// Cost of the start solution
+    pub cost_before: usize,
+    /// Cost of the best solution found
+    pub cost_after: usize,
+    pub iterations: usize,
+    /// One entry per destroy operator, in the order of [`DestroyOperator::ALL`]
+    pub destroy: Vec<OperatorStats>,
+    /// One entry per repair operator, in the order of [`RepairOperator::ALL`]
+    pub repair: Vec<OperatorStats>,
+    /// Whether the time limit stopped the run early
+    pub timed_out: bool,
+}
+
+impl fmt::Display for AlnsStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cost {} -> {} ({} iterations)",
+            self.cost_before, self.cost_after, self.iterations
+        )?;
+        if self.timed_out {
+            write!(f, ", stopped by time limit")?;
+        }
+        Ok(())
+    }
+}
+
+/// Improves a valid batching by destroying and repairing parts of it
+///
+/// Minimizes the tour cost plus rest cost of the batches plus the penalty for split orders. The
+/// articles of a gene of the encoding are removed and inserted together, and insertions keep every
+/// batch within the max weight. Operators that lead to better solutions get picked more often.
+pub fn alns_batches<'a>(
+    model: &'a Model,
+    batched_articles: &BatchedArticles<'a>,
+    encoding: BatchEncoding,
+    params: &AlnsParams,
+) -> (BatchedArticles<'a>, AlnsStats) {
+    let evaluator = Evaluator::new(model);
+    let mut search = Search::new(
+        evaluator.batching(batched_articles.batch_mapping().clone()),
+        article_groups(model, encoding),
+    );
+    let mut rng = Prng::seed_from_u64(params.seed);
+
+    let cost_before = search.batching.cost();
+    let (mut current, mut best) = (cost_before, cost_before);
+    let mut best_mapping = search.batching.batch_mapping().clone();
+    let mut destroy = Operators::new(DestroyOperator::ALL.len());
+    let mut repair = Operators::new(RepairOperator::ALL.len());
+    let max_removed = ((search.groups.len() as f64 * params.destroy_fraction) as usize).max(1);
+    let mut temperature = params.start_temperature;
+    let mut num_iterations = 0;
+    let mut timed_out = false;
+
+    for iteration in 0..params.iterations {
+        if params
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            timed_out = true;
+            break;
+        }
+        if iteration > 0 && iteration % params.segment_length.max(1) == 0 {
+            destroy.update_weights(params.reaction);
+            repair.update_weights(params.reaction);
+        }
+        num_iterations += 1;
+
+        let previous = search.batching.batch_mapping().clone();
+        let (destroy_idx, repair_idx) = (destroy.pick(&mut rng), repair.pick(&mut rng));
+        let num_removed = rng.gen_range(1..=max_removed);
+        let removed = search.destroy(DestroyOperator::ALL[destroy_idx], num_removed, &mut rng);
+        search.repair(RepairOperator::ALL[repair_idx], removed);
+
+        let cost = search.batching.cost();
+        let accept = cost <= current
+            || rng.gen::<f64>() < ((current as f64 - cost as f64) / temperature).exp();
+        temperature *= params.cooling;
+
+        let score = if cost < best {
+            best = cost;
+            best_mapping = search.batching.batch_mapping().clone();
+            destroy.stats[destroy_idx].improvements += 1;
+            repair.stats[repair_idx].improvements += 1;
+            SCORE_BEST
+        } else if cost < current {
+            SCORE_BETTER
+        } else if accept {
+            SCORE_ACCEPTED
+        } else {
+            0.0
+        };
+        destroy.record(destroy_idx, score, accept);
+        repair.record(repair_idx, score, accept);
+
+        if accept {
+            current = cost;
+        } else {
+            search.restore(&previous);
+        }
+    }
+
+    let stats = AlnsStats {
+        cost_before,
+        cost_after: best,
+        iterations: num_iterations,
+        destroy: destroy.into_stats(DestroyOperator::ALL.map(|operator| operator.name())),
+        repair: repair.into_stats(RepairOperator::ALL.map(|operator| operator.name())),
+        timed_out,
+    };
+    (
+        BatchedArticles::from_batch_mapping(best_mapping, model),
+        stats,
+    )
+}
+
+/// Adaptive weights and statistics of a kind of operators
+struct Operators {
+    weights: Vec<f64>,
+    /// Sum of scores and number of uses of every operator in the current segment
+    segment: Vec<(f64, usize)>,
+    stats: Vec<OperatorStats>,
+}
+
+impl Operators {
+    fn new(num_operators: usize) -> Operators {
+        Operators {
+            weights: vec![1.0; num_operators],
+            segment: vec![(0.0, 0); num_operators],
+            stats: vec![
+                OperatorStats {
+                    name: "",
+                    uses: 0,
+                    improvements: 0,
+                    accepted: 0,
+                    weight: 1.0,
+                };
+                num_operators
+            ],
+        }
+    }
+
+    /// Roulette wheel selection by weight
+    fn pick(&self, rng: &mut Prng) -> usize {
+        let mut target = rng.gen::<f64>() * self.weights.iter().sum::<f64>();
+        for (idx, weight) in self.weights.iter().enumerate() {
+            if target < *weight {
+                return idx;
+            }
+            target -= weight;
+        }
+        self.weights.len() - 1
+    }
+
+    fn record(&mut self, idx: usize, score: f64, accepted: bool) {
+        self.segment[idx].0 += score;
+        self.segment[idx].1 += 1;
+        self.stats[idx].uses += 1;
+        self.stats[idx].accepted += accepted as usize;
+    }
+
+    /// Moves the weight of every operator used in the segment towards its average score
+    fn update_weights(&mut self, reaction: f64) {
+        for (weight, (score, uses)) in self.weights.iter_mut().zip(self.segment.iter_mut()) {
+            if *uses > 0 {
+                *weight = (1.0 - reaction) * *weight + reaction * *score / *uses as f64;
+            }
+            (*score, *uses) = (0.0, 0);
+        }
+    }
+
+    fn into_stats<const N: usize>(self, names: [&'static str; N]) -> Vec<OperatorStats> {
+        self.stats
+            .into_iter()
+            .zip(self.weights)
+            .zip(names)
+            .map(|((stats, weight), name)| OperatorStats {
+                name,
+                weight,
+                ..stats
+            })
+            .collect()
+    }
+}
+
+/// A batching whose genes (groups of articles) get removed into a pool batch and inserted again
+struct Search<'a> {
+    batching: Batching<'a>,
+    /// Batch past the ids of the batch mapping that holds the removed groups, empty between steps
+    pool: BatchId,
+    groups: Vec<ArticleGroup>,
+    group_volumes: Vec<Volume>,
+    /// Groups of every order
+    orders: Vec<Vec<usize>>,
+    /// Groups of every aisle
+    aisles: Vec<Vec<usize>>,
+}
+
+impl<'a> Search<'a> {
+    fn new(mut batching: Batching<'a>, groups: Vec<ArticleGroup>) -> Search<'a> {
+        let pool = batching.add_batch();
+        let group_volumes = groups
+            .iter()
+            .map(|group| group.iter().map(|idx| batching.article(*idx).volume).sum())
+            .collect();
+
+        let mut orders: BTreeMap<ID, Vec<usize>> = BTreeMap::new();
+        let mut aisles: BTreeMap<(ID, ID), Vec<usize>> = BTreeMap::new();
+        for (group_idx, group) in groups.iter().enumerate() {
+            for idx in group.iter() {
+                let article = batching.article(*idx);
+                let location = (article.location.warehouse, article.location.aisle);
+                for groups in [
+                    orders.entry(article.order_id).or_default(),
+                    aisles.entry(location).or_default(),
+                ] {
+                    if groups.last() != Some(&group_idx) {
+                        groups.push(group_idx);
+                    }
+                }
+            }
+        }
+
+        Search {
+            batching,
+            pool,
+            groups,
+            group_volumes,
+            orders: orders.into_values().collect(),
+            aisles: aisles.into_values().collect(),
+        }
+    }
+
+    fn batch_of(&self, group: usize) -> BatchId {
+        self.batching.batch_of(self.groups[group][0])
+    }
+
+    fn move_group(&mut self, group: usize, to: BatchId) {
+        for idx in self.groups[group].iter() {
+            self.batching.move_article(*idx, to);
+        }
+    }
+
+    /// First empty batch, other than the pool
+    ///
+    /// As there are as many batch ids as articles, a batch is empty whenever the pool is not.
+    fn empty_batch(&self) -> BatchId {
+        (0..self.batching.num_articles())
+            .find(|batch_id| self.batching.is_empty_batch(*batch_id))
+            .expect("empty batch")
+    }
+
+    /// Moves every article back into its batch of the given mapping
+    fn restore(&mut self, batch_mapping: &[BatchId]) {
+        for (idx, batch_id) in batch_mapping.iter().enumerate() {
+            self.batching.move_article(idx, *batch_id);
+        }
+    }
+
+    /// Moves at least `num_removed` groups (or all there are) into the pool and returns them
+    fn destroy(
+        &mut self,
+        operator: DestroyOperator,
+        num_removed: usize,
+        rng: &mut Prng,
+    ) -> Vec<usize> {
+        let num_removed = num_removed.min(self.groups.len());
+        let mut removed = Vec::new();
+
+        match operator {
+            DestroyOperator::RandomAisle => {
+                while removed.len() < num_removed {
+                    let aisle = self.aisles[rng.gen_range(0..self.aisles.len())].clone();
+                    self.remove_groups(&aisle, &mut removed);
+                }
+            }
+            DestroyOperator::WorstBatches => {
+                let mut groups_by_batch: BTreeMap<BatchId, Vec<usize>> = BTreeMap::new();
+                for group in 0..self.groups.len() {
+                    groups_by_batch
+                        .entry(self.batch_of(group))
+                        .or_default()
+                        .push(group);
+                }
+                // the noise varies the order of batches with a similar cost per article
+                let mut batches = groups_by_batch
+                    .into_iter()
+                    .map(|(batch_id, groups)| {
+                        let num_articles =
+                            groups.iter().map(|g| self.groups[*g].len()).sum::<usize>();
+                        let cost = self.batching.batch_cost(batch_id) as f64 / num_articles as f64;
+                        (cost * rng.gen_range(0.8..1.2), groups)
+                    })
+                    .collect::<Vec<_>>();
+                batches.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+                for (_, groups) in batches {
+                    if removed.len() >= num_removed {
+                        break;
+                    }
+                    self.remove_groups(&groups, &mut removed);
+                }
+            }
+            DestroyOperator::RandomOrder => {
+                while removed.len() < num_removed {
+                    let order = self.orders[rng.gen_range(0..self.orders.len())].clone();
+                    self.remove_groups(&order, &mut removed);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Moves the groups that are not in the pool yet into it
+    fn remove_groups(&mut self, groups: &[usize], removed: &mut Vec<usize>) {
+        for group in groups.iter() {
+            if self.batch_of(*group) != self.pool {
+                self.move_group(*group, self.pool);
+                removed.push(*group);
+            }
+        }
+    }
+
+    /// Inserts the removed groups from the pool into the batches, one at a time, until the pool is
+    /// empty
+    fn repair(&mut self, operator: RepairOperator, mut removed: Vec<usize>) {
+        let batch_ids = self
+            .batching
+            .non_empty_batch_ids()
+            .into_iter()
+            .filter(|batch_id| *batch_id != self.pool)
+            .collect::<Vec<_>>();
+        let mut candidates = removed
+            .iter()
+            .map(|group| {
+                let mut candidates = BTreeMap::new();
+                for batch_id in batch_ids.iter() {
+                    self.add_candidate(&mut candidates, *group, *batch_id);
+                }
+                self.add_candidate(&mut candidates, *group, NEW_BATCH);
+                candidates
+            })
+            .collect::<Vec<_>>();
+
+        while !removed.is_empty() {
+            let next = match operator {
+                RepairOperator::Greedy => (0..removed.len())
+                    .min_by_key(|idx| {
+                        cheapest(&candidates[*idx]).map_or(i64::MAX, |(_, cost)| cost)
+                    })
+                    .expect("removed groups"),
+                RepairOperator::Regret => (0..removed.len())
+                    .max_by_key(|idx| {
+                        let cost = cheapest(&candidates[*idx]).map_or(i64::MAX, |(_, cost)| cost);
+                        (regret(&candidates[*idx]), -cost)
+                    })
+                    .expect("removed groups"),
+            };
+            let group = removed.swap_remove(next);
+            let batch_id = match cheapest(&candidates.swap_remove(next)).expect("empty batch") {
+                (NEW_BATCH, _) => self.empty_batch(),
+                (batch_id, _) => batch_id,
+            };
+            self.move_group(group, batch_id);
+
+            // only the costs of inserting into the changed batch change
+            for (group, candidates) in removed.iter().zip(candidates.iter_mut()) {
+                candidates.remove(&batch_id);
+                self.add_candidate(candidates, *group, batch_id);
+                candidates.remove(&NEW_BATCH);
+                self.add_candidate(candidates, *group, NEW_BATCH);
+            }
+        }
+    }
+
+    /// Adds the extra cost of inserting the group into the batch, if the group fits into it
+    fn add_candidate(
+        &mut self,
+        candidates: &mut BTreeMap<BatchId, i64>,
+        group: usize,
+        batch_id: BatchId,
+    ) {
+        let batch_id = match batch_id {
+            NEW_BATCH => self.empty_batch(),
+            batch_id => batch_id,
+        };
+        if self.batching.volume(batch_id) + self.group_volumes[group]
+            > self.batching.cost_model().max_weight_per_batch
+            && !self.batching.is_empty_batch(batch_id)
+        {
+            return;
+        }
+
+        // the cost of the pool changes as well, which must not count
+        let pool = self.pool;
+        let cost_without = self.batching.cost() as i64 - self.batching.batch_cost(pool) as i64;
+        self.move_group(group, batch_id);
+        let cost_with = self.batching.cost() as i64 - self.batching.batch_cost(pool) as i64;
+        self.move_group(group, pool);
+
+        let key = if self.batching.is_empty_batch(batch_id) {
+            NEW_BATCH
+        } else {
+            batch_id
+        };
+        candidates.insert(key, cost_with - cost_without);
+    }
+}
+
+/// Batch with the cheapest insertion and its extra cost
+fn cheapest(candidates: &BTreeMap<BatchId, i64>) -> Option<(BatchId, i64)> {
+    candidates
+        .iter()
+        .min_by_key(|(_, cost)| **cost)
+        .map(|(batch_id, cost)| (*batch_id, *cost))
+}
+
+/// Extra cost of the second cheapest insertion over the cheapest one
+fn regret(candidates: &BTreeMap<BatchId, i64>) -> i64 {
+    let mut costs = candidates.values().copied().collect::<Vec<_>>();
+    costs.sort_unstable();
+    match costs.as_slice() {
+        [cheapest, second, ..] => second - cheapest,
+        _ => i64::MAX,
+    }
+}
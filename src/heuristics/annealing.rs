@@ -0,0 +1,377 @@
+//! Simulated annealing over the batches and over the waives, an alternative to the genetic searches
+use std::fmt;
+use std::time::Instant;
+
+use genevo::random::{Prng, Rng, SeedableRng};
+
+use crate::batching::{Batching, Evaluator};
+use crate::ga::batches::{WaiveId, WaiveMapping, WaivedBatches};
+use crate::ga::orders::{article_groups, ArticleGroup, BatchEncoding, BatchedArticles};
+use crate::model::*;
+use crate::waiving::Waiving;
+
+/// How the temperature falls from the start to the end temperature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Cooling {
+    /// Multiplies the temperature by the same factor at every step
+    #[default]
+    Geometric,
+    /// Lowers the temperature by the same amount at every step
+    Linear,
+}
+
+/// Changes that lead from a solution to one of its neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaMoves {
+    /// Moves an article (or gene) into the batch of another one, or a batch into another waive
+    Move,
+    /// Swaps two articles (or genes) of different batches, or two batches of different waives
+    Swap,
+    /// Moves or swaps, each with the same probability
+    #[default]
+    Both,
+}
+
+/// Parameters of a single simulated annealing run
+#[derive(Debug, Clone, Copy)]
+pub struct SaParams {
+    /// Temperature of the first step, in units of cost
+    pub start_temperature: f64,
+    /// Temperature of the last step
+    pub end_temperature: f64,
+    /// Number of temperatures from the start to the end temperature
+    pub temperature_steps: usize,
+    /// Number of changes tried at every temperature
+    pub moves_per_temperature: usize,
+    pub cooling: Cooling,
+    pub moves: SaMoves,
+    /// Seed for all random decisions of the run
+    pub seed: u64,
+    /// Point in time at which the run stops and returns the best solution found so far
+    pub deadline: Option<Instant>,
+}
+
+impl Default for SaParams {
+    fn default() -> Self {
+        SaParams {
+            start_temperature: 10.0,
+            end_temperature: 0.1,
+            temperature_steps: 100,
+            moves_per_temperature: 100,
+            cooling: Cooling::default(),
+            moves: SaMoves::default(),
+            seed: 0,
+            deadline: None,
+        }
+    }
+}
+
+impl SaParams {
+    /// Temperature at the given step, from the start temperature down to the end temperature
+    fn temperature(&self, step: usize) -> f64 {
+        let progress = match self.temperature_steps {
+            0 | 1 => 0.0,
+            steps => step as f64 / (steps - 1) as f64,
+        };
+
+        match self.cooling {
+            Cooling::Geometric => {
+                self.start_temperature
+                    * (self.end_temperature / self.start_temperature).powf(progress)
+            }
+            Cooling::Linear => {
+                self.start_temperature + (self.end_temperature - self.start_temperature) * progress
+            }
+        }
+    }
+}
+
+/// Statistics of a finished simulated annealing run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaStats {
+    /// Cost of the start solution
+    pub cost_before: usize,
+    /// Cost of the best solution found
+    pub cost_after: usize,
+    /// Number of tried changes
+    pub iterations: usize,
+    /// Number of changes that were kept
+    pub accepted: usize,
+    /// Number of changes that led to a new best solution
+    pub improvements: usize,
+    /// Whether the time limit stopped the run before it cooled down
+    pub timed_out: bool,
+}
+
+impl fmt::Display for SaStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cost {} -> {} ({} iterations, {} accepted, {} improvements)",
+            self.cost_before, self.cost_after, self.iterations, self.accepted, self.improvements
+        )?;
+        if self.timed_out {
+            write!(f, ", stopped by time limit")?;
+        }
+        Ok(())
+    }
+}
+
+/// Anneals a valid batching, moving and swapping the articles of the genes of the encoding
+///
+/// Minimizes the tour cost plus rest cost of the batches plus the penalty for split orders. Only
+/// changes that keep every batch within the max weight are tried.
+pub fn anneal_batches<'a>(
+    model: &'a Model,
+    batched_articles: &BatchedArticles<'a>,
+    encoding: BatchEncoding,
+    params: &SaParams,
+) -> (BatchedArticles<'a>, SaStats) {
+    let evaluator = Evaluator::new(model);
+    let groups = article_groups(model, encoding);
+    let group_volumes = groups
+        .iter()
+        .map(|group| group.iter().map(|idx| evaluator.article(*idx).volume).sum())
+        .collect();
+    let mut state = BatchState {
+        batching: evaluator.batching(batched_articles.batch_mapping().clone()),
+        groups,
+        group_volumes,
+    };
+
+    let (batch_mapping, stats) = anneal(&mut state, params);
+    (
+        BatchedArticles::from_batch_mapping(batch_mapping, model),
+        stats,
+    )
+}
+
+/// Anneals a valid waiving of the batches, moving and swapping batches between waives
+///
+/// Minimizes the rest cost of the waives plus the penalties for orders split across waives. Only
+/// changes that keep every waive within the max articles are tried.
+pub fn anneal_waives<'a>(
+    model: &Model,
+    batched_articles: &'a BatchedArticles,
+    waive_mapping: &WaiveMapping,
+    params: &SaParams,
+) -> (WaivedBatches<'a>, SaStats) {
+    let mut state = Waiving::new(model.cost_model(), batched_articles, waive_mapping);
+
+    let (waive_mapping, stats) = anneal(&mut state, params);
+    (
+        WaivedBatches::from_waive_mapping(waive_mapping, batched_articles),
+        stats,
+    )
+}
+
+/// A solution that changes in place, as seen by the annealing
+trait Neighborhood {
+    type Change;
+
+    fn cost(&self) -> usize;
+
+    /// Applies a random change that keeps the solution valid, `None` if the drawn change does not
+    fn apply_random(&mut self, rng: &mut Prng, moves: SaMoves) -> Option<Self::Change>;
+
+    fn undo(&mut self, change: Self::Change);
+
+    fn mapping(&self) -> &Vec<usize>;
+}
+
+/// Runs the annealing from the given solution and returns the best mapping found
+fn anneal<N: Neighborhood>(state: &mut N, params: &SaParams) -> (Vec<usize>, SaStats) {
+    let mut rng = Prng::seed_from_u64(params.seed);
+    let mut best = (state.mapping().clone(), state.cost());
+    let mut stats = SaStats {
+        cost_before: state.cost(),
+        cost_after: state.cost(),
+        iterations: 0,
+        accepted: 0,
+        improvements: 0,
+        timed_out: false,
+    };
+
+    for step in 0..params.temperature_steps {
+        if params
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            stats.timed_out = true;
+            break;
+        }
+
+        let temperature = params.temperature(step);
+        for _ in 0..params.moves_per_temperature {
+            stats.iterations += 1;
+            let cost = state.cost();
+            let change = match state.apply_random(&mut rng, params.moves) {
+                Some(change) => change,
+                None => continue,
+            };
+
+            let new_cost = state.cost();
+            let accept = new_cost <= cost
+                || rng.gen::<f64>() < ((cost as f64 - new_cost as f64) / temperature).exp();
+            if !accept {
+                state.undo(change);
+                continue;
+            }
+
+            stats.accepted += 1;
+            if new_cost < best.1 {
+                best = (state.mapping().clone(), new_cost);
+                stats.improvements += 1;
+            }
+        }
+    }
+
+    stats.cost_after = best.1;
+    (best.0, stats)
+}
+
+/// Draws whether the next change is a swap
+fn draw_swap(rng: &mut Prng, moves: SaMoves) -> bool {
+    match moves {
+        SaMoves::Move => false,
+        SaMoves::Swap => true,
+        SaMoves::Both => rng.gen_bool(0.5),
+    }
+}
+
+/// A batching whose genes (groups of articles) move between batches
+struct BatchState<'a> {
+    batching: Batching<'a>,
+    groups: Vec<ArticleGroup>,
+    group_volumes: Vec<Volume>,
+}
+
+enum BatchChange {
+    Moved { group: usize, from: usize },
+    Swapped { group: usize, other_group: usize },
+}
+
+impl<'a> BatchState<'a> {
+    fn batch_of(&self, group: usize) -> usize {
+        self.batching.batch_of(self.groups[group][0])
+    }
+
+    fn move_group(&mut self, group: usize, to: usize) {
+        for idx in self.groups[group].iter() {
+            self.batching.move_article(*idx, to);
+        }
+    }
+}
+
+impl<'a> Neighborhood for BatchState<'a> {
+    type Change = BatchChange;
+
+    fn cost(&self) -> usize {
+        self.batching.cost()
+    }
+
+    /// Moves a group into the batch of another group or into an empty batch, or swaps two groups
+    fn apply_random(&mut self, rng: &mut Prng, moves: SaMoves) -> Option<BatchChange> {
+        if self.groups.is_empty() {
+            return None;
+        }
+
+        let max_weight = self.batching.cost_model().max_weight_per_batch;
+        let group = rng.gen_range(0..self.groups.len());
+        let from = self.batch_of(group);
+        let volume = self.group_volumes[group];
+        if draw_swap(rng, moves) {
+            let other_group = rng.gen_range(0..self.groups.len());
+            let to = self.batch_of(other_group);
+            let other_volume = self.group_volumes[other_group];
+            if from == to
+                || self.batching.volume(to) + volume - other_volume > max_weight
+                || self.batching.volume(from) + other_volume - volume > max_weight
+            {
+                return None;
+            }
+            self.move_group(group, to);
+            self.move_group(other_group, from);
+            Some(BatchChange::Swapped { group, other_group })
+        } else {
+            // one draw more than there are groups, which stands for an empty batch
+            let other_group = rng.gen_range(0..=self.groups.len());
+            let to = if other_group < self.groups.len() {
+                self.batch_of(other_group)
+            } else {
+                self.batching.empty_batch_id()?
+            };
+            if from == to || self.batching.volume(to) + volume > max_weight {
+                return None;
+            }
+            self.move_group(group, to);
+            Some(BatchChange::Moved { group, from })
+        }
+    }
+
+    fn undo(&mut self, change: BatchChange) {
+        match change {
+            BatchChange::Moved { group, from } => self.move_group(group, from),
+            BatchChange::Swapped { group, other_group } => {
+                let (batch, other_batch) = (self.batch_of(group), self.batch_of(other_group));
+                self.move_group(group, other_batch);
+                self.move_group(other_group, batch);
+            }
+        }
+    }
+
+    fn mapping(&self) -> &Vec<usize> {
+        self.batching.batch_mapping()
+    }
+}
+
+enum WaiveChange {
+    Moved { idx: usize, from: WaiveId },
+    Swapped { idx: usize, other_idx: usize },
+}
+
+impl<'a> Neighborhood for Waiving<'a> {
+    type Change = WaiveChange;
+
+    fn cost(&self) -> usize {
+        Waiving::cost(self)
+    }
+
+    fn apply_random(&mut self, rng: &mut Prng, moves: SaMoves) -> Option<WaiveChange> {
+        if self.num_batches() == 0 {
+            return None;
+        }
+
+        let idx = rng.gen_range(0..self.num_batches());
+        let other_idx = rng.gen_range(0..self.num_batches());
+        let (from, to) = (self.waive_of(idx), self.waive_of(other_idx));
+        if from == to {
+            return None;
+        }
+
+        if draw_swap(rng, moves) {
+            if !self.fits_swapped(idx, other_idx) {
+                return None;
+            }
+            self.swap_batches(idx, other_idx);
+            Some(WaiveChange::Swapped { idx, other_idx })
+        } else {
+            if !self.fits(idx, to) {
+                return None;
+            }
+            self.move_batch(idx, to);
+            Some(WaiveChange::Moved { idx, from })
+        }
+    }
+
+    fn undo(&mut self, change: WaiveChange) {
+        match change {
+            WaiveChange::Moved { idx, from } => self.move_batch(idx, from),
+            WaiveChange::Swapped { idx, other_idx } => self.swap_batches(idx, other_idx),
+        }
+    }
+
+    fn mapping(&self) -> &Vec<usize> {
+        self.waive_mapping()
+    }
+}
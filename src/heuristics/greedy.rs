@@ -0,0 +1,149 @@
+//! Fast constructive heuristics for batches and waives
+use std::collections::BTreeSet;
+
+use crate::ga::batches::{WaiveMapping, WaivedBatches};
+use crate::ga::orders::{
+    article_groups, ArticleGroup, BatchEncoding, BatchMapping, BatchedArticles,
+};
+use crate::model::*;
+
+/// Packs the articles into batches by first-fit-decreasing, aisle by aisle
+pub fn greedy_batches(model: &Model) -> BatchedArticles<'_> {
+    greedy_encoded_batches(model, BatchEncoding::Articles)
+}
+
+/// Packs the genes of an encoding into batches by first-fit-decreasing, aisle by aisle
+pub fn greedy_encoded_batches(model: &Model, encoding: BatchEncoding) -> BatchedArticles<'_> {
+    BatchedArticles::from_batch_mapping(greedy_batch_mapping(model, encoding), model)
+}
+
+/// Packs the batches into waives by first-fit-decreasing on their number of articles
+pub fn greedy_waives<'a>(
+    model: &'a Model,
+    batched_articles: &'a BatchedArticles,
+) -> WaivedBatches<'a> {
+    let waive_mapping = greedy_waive_mapping(model, batched_articles);
+    WaivedBatches::from_waive_mapping(waive_mapping, batched_articles)
+}
+
+/// A batch under construction
+#[derive(Default)]
+struct OpenBatch {
+    volume: Volume,
+    warehouses: BTreeSet<ID>,
+    aisles: BTreeSet<(ID, ID)>,
+}
+
+/// Visits the articles ordered by warehouse, aisle and decreasing volume and puts each into the
+/// first batch with enough room, preferring batches that already visit its aisle or warehouse
+///
+/// With [`BatchEncoding::Orders`], the articles of an order are packed together, visited by their
+/// first warehouse and aisle and by decreasing volume of the order.
+pub(crate) fn greedy_batch_mapping(model: &Model, encoding: BatchEncoding) -> BatchMapping {
+    let max_weight = model.cost_model().max_weight_per_batch;
+    let articles = model.get_ordered_articles();
+    let groups = article_groups(model, encoding);
+
+    let volume = |group: &ArticleGroup| {
+        group
+            .iter()
+            .map(|idx| articles[*idx].volume)
+            .sum::<Volume>()
+    };
+    let aisles = |group: &ArticleGroup| {
+        group
+            .iter()
+            .map(|idx| {
+                let location = articles[*idx].location;
+                (location.warehouse, location.aisle)
+            })
+            .collect::<BTreeSet<_>>()
+    };
+
+    let mut groups = groups
+        .iter()
+        .map(|group| (group, volume(group), aisles(group)))
+        .collect::<Vec<_>>();
+    groups.sort_by_key(|(_, volume, aisles)| {
+        let first_aisle = aisles.first().copied().unwrap_or_default();
+        (first_aisle, std::cmp::Reverse(*volume))
+    });
+
+    let mut batch_mapping = vec![0; articles.len()];
+    let mut batches: Vec<OpenBatch> = Vec::new();
+
+    for (group, volume, aisles) in groups {
+        let warehouses = aisles
+            .iter()
+            .map(|(warehouse, _)| *warehouse)
+            .collect::<BTreeSet<_>>();
+        let fits = |batch: &OpenBatch| batch.volume + volume <= max_weight;
+
+        let batch_id = batches
+            .iter()
+            .position(|batch| fits(batch) && !batch.aisles.is_disjoint(&aisles))
+            .or_else(|| {
+                batches
+                    .iter()
+                    .position(|batch| fits(batch) && !batch.warehouses.is_disjoint(&warehouses))
+            })
+            .or_else(|| batches.iter().position(fits))
+            .unwrap_or_else(|| {
+                batches.push(OpenBatch::default());
+                batches.len() - 1
+            });
+
+        let batch = &mut batches[batch_id];
+        batch.volume += volume;
+        batch.warehouses.extend(warehouses);
+        batch.aisles.extend(aisles);
+        group.iter().for_each(|idx| batch_mapping[*idx] = batch_id);
+    }
+
+    batch_mapping
+}
+
+/// A waive under construction
+#[derive(Default)]
+struct OpenWaive {
+    num_articles: usize,
+    order_ids: BTreeSet<ID>,
+}
+
+/// Visits the batches by decreasing number of articles and puts each into the first waive with
+/// enough room, preferring waives that already contain one of its orders
+pub(crate) fn greedy_waive_mapping(
+    model: &Model,
+    batched_articles: &BatchedArticles,
+) -> WaiveMapping {
+    let max_articles = model.cost_model().max_articles_per_waive;
+    let batches = batched_articles.to_batches();
+
+    let mut batch_indices = (0..batches.len()).collect::<Vec<_>>();
+    batch_indices.sort_by_key(|idx| std::cmp::Reverse(batches[*idx].num_articles()));
+
+    let mut waive_mapping = vec![0; batches.len()];
+    let mut waives: Vec<OpenWaive> = Vec::new();
+
+    for idx in batch_indices {
+        let num_articles = batches[idx].num_articles();
+        let order_ids = batches[idx].order_ids_in_batch();
+        let fits = |waive: &OpenWaive| waive.num_articles + num_articles <= max_articles;
+
+        let waive_id = waives
+            .iter()
+            .position(|waive| fits(waive) && !waive.order_ids.is_disjoint(&order_ids))
+            .or_else(|| waives.iter().position(fits))
+            .unwrap_or_else(|| {
+                waives.push(OpenWaive::default());
+                waives.len() - 1
+            });
+
+        let waive = &mut waives[waive_id];
+        waive.num_articles += num_articles;
+        waive.order_ids.extend(order_ids);
+        waive_mapping[idx] = waive_id;
+    }
+
+    waive_mapping
+}
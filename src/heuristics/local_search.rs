@@ -0,0 +1,290 @@
+//! Local search that improves a batching by small changes
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::time::Instant;
+
+use crate::batching::{Batching, Evaluator};
+use crate::ga::orders::{article_groups, ArticleGroup, BatchEncoding, BatchId, BatchedArticles};
+use crate::model::*;
+
+/// Statistics of a finished local search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalSearchStats {
+    /// Tour cost plus rest cost of the batches before the local search
+    pub cost_before: usize,
+    /// Tour cost plus rest cost of the batches after the local search
+    pub cost_after: usize,
+    /// Number of articles moved into another batch
+    pub moves: usize,
+    /// Number of article pairs swapped between two batches
+    pub swaps: usize,
+    /// Number of batches merged into another batch
+    pub merges: usize,
+    /// Whether the time limit stopped the search before it ran out of improvements
+    pub timed_out: bool,
+}
+
+impl LocalSearchStats {
+    pub fn improvement(&self) -> usize {
+        self.cost_before - self.cost_after
+    }
+}
+
+impl fmt::Display for LocalSearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cost {} -> {} ({} moves, {} swaps, {} merges)",
+            self.cost_before, self.cost_after, self.moves, self.swaps, self.merges
+        )?;
+        if self.timed_out {
+            write!(f, ", stopped by time limit")?;
+        }
+        Ok(())
+    }
+}
+
+/// Improves a valid batching until no single move, swap or merge makes it cheaper, or until the
+/// deadline
+///
+/// Moves and swaps take the articles of a gene of the encoding together, so that the batching
+/// keeps them in one batch. Only changes that keep every batch within the max weight are applied.
+/// Every pass tries all merges, then all moves, then the swaps between batches that visit each
+/// other's aisles, and keeps going after an improvement.
+pub fn improve_batches<'a>(
+    model: &'a Model,
+    batched_articles: &BatchedArticles<'a>,
+    encoding: BatchEncoding,
+    deadline: Option<Instant>,
+) -> (BatchedArticles<'a>, LocalSearchStats) {
+    let evaluator = Evaluator::new(model);
+    let mut search = Search::new(
+        evaluator.batching(batched_articles.batch_mapping().clone()),
+        article_groups(model, encoding),
+        deadline,
+    );
+    let cost_before = search.batching.cost();
+
+    let (mut moves, mut swaps, mut merges) = (0, 0, 0);
+    loop {
+        let pass_merges = search.merge_pass();
+        let pass_moves = search.move_pass();
+        let pass_swaps = search.swap_pass();
+        merges += pass_merges;
+        moves += pass_moves;
+        swaps += pass_swaps;
+
+        if pass_merges + pass_moves + pass_swaps == 0 || search.timed_out() {
+            break;
+        }
+    }
+
+    let stats = LocalSearchStats {
+        cost_before,
+        cost_after: search.batching.cost(),
+        moves,
+        swaps,
+        merges,
+        timed_out: search.timed_out,
+    };
+    let batched_articles =
+        BatchedArticles::from_batch_mapping(search.batching.into_batch_mapping(), model);
+
+    (batched_articles, stats)
+}
+
+/// A batching with the groups of every batch and the groups of every aisle
+struct Search<'a> {
+    batching: Batching<'a>,
+    groups: Vec<ArticleGroup>,
+    /// Visited aisles of every group, as `(warehouse, aisle)`
+    group_aisles: Vec<BTreeSet<(ID, ID)>>,
+    /// Groups with an article in the aisle, by warehouse and aisle
+    aisle_groups: BTreeMap<(ID, ID), Vec<usize>>,
+    /// Groups of every batch, by batch id
+    members: Vec<BTreeSet<usize>>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+}
+
+impl<'a> Search<'a> {
+    fn new(batching: Batching<'a>, groups: Vec<ArticleGroup>, deadline: Option<Instant>) -> Self {
+        let group_aisles = groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|idx| {
+                        let location = batching.article(*idx).location;
+                        (location.warehouse, location.aisle)
+                    })
+                    .collect::<BTreeSet<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut aisle_groups: BTreeMap<(ID, ID), Vec<usize>> = BTreeMap::new();
+        for (group, aisles) in group_aisles.iter().enumerate() {
+            for aisle in aisles.iter() {
+                aisle_groups.entry(*aisle).or_default().push(group);
+            }
+        }
+        let mut members = vec![BTreeSet::new(); batching.num_articles()];
+        for (group, articles) in groups.iter().enumerate() {
+            members[batching.batch_of(articles[0])].insert(group);
+        }
+
+        Search {
+            batching,
+            groups,
+            group_aisles,
+            aisle_groups,
+            members,
+            deadline,
+            timed_out: false,
+        }
+    }
+
+    /// Whether the deadline has passed, remembered once it has
+    fn timed_out(&mut self) -> bool {
+        if !self.timed_out {
+            self.timed_out = self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline);
+        }
+        self.timed_out
+    }
+
+    fn batch_of(&self, group: usize) -> BatchId {
+        self.batching.batch_of(self.groups[group][0])
+    }
+
+    fn volume(&self, group: usize) -> Volume {
+        self.groups[group]
+            .iter()
+            .map(|idx| self.batching.article(*idx).volume)
+            .sum()
+    }
+
+    fn move_group(&mut self, group: usize, to: BatchId) {
+        let from = self.batch_of(group);
+        for idx in self.groups[group].iter() {
+            self.batching.move_article(*idx, to);
+        }
+        self.members[from].remove(&group);
+        self.members[to].insert(group);
+    }
+
+    /// Merges every pair of batches that fits into one batch and gets cheaper that way
+    fn merge_pass(&mut self) -> usize {
+        let max_weight = self.batching.cost_model().max_weight_per_batch;
+        let batch_ids = self.batching.non_empty_batch_ids();
+
+        let mut merges = 0;
+        for (pos, from) in batch_ids.iter().enumerate() {
+            if self.timed_out() {
+                break;
+            }
+
+            for to in batch_ids.iter().skip(pos + 1) {
+                if self.batching.volume(*from) + self.batching.volume(*to) > max_weight {
+                    continue;
+                }
+
+                let cost = self.batching.batch_cost(*from) + self.batching.batch_cost(*to);
+                if self.batching.merged_cost(*from, *to) < cost {
+                    for group in std::mem::take(&mut self.members[*from]) {
+                        self.move_group(group, *to);
+                    }
+                    merges += 1;
+                    break;
+                }
+            }
+        }
+
+        merges
+    }
+
+    /// Moves every group into the first other batch where it fits and gets cheaper
+    fn move_pass(&mut self) -> usize {
+        let max_weight = self.batching.cost_model().max_weight_per_batch;
+        let batch_ids = self.batching.non_empty_batch_ids();
+
+        let mut moves = 0;
+        for group in 0..self.groups.len() {
+            if self.timed_out() {
+                break;
+            }
+
+            let from = self.batch_of(group);
+            let volume = self.volume(group);
+            for to in batch_ids.iter().filter(|to| **to != from) {
+                if self.members[*to].is_empty() || self.batching.volume(*to) + volume > max_weight {
+                    continue;
+                }
+
+                let cost = self.batching.cost();
+                self.move_group(group, *to);
+                if self.batching.cost() < cost {
+                    moves += 1;
+                    break;
+                }
+                self.move_group(group, from);
+            }
+        }
+
+        moves
+    }
+
+    /// Swaps every group with the first group of another batch where both fit and get cheaper
+    ///
+    /// Only tries batches that visit an aisle of the group and groups in aisles its own batch
+    /// visits, as other swaps add aisles to both batches.
+    fn swap_pass(&mut self) -> usize {
+        let max_weight = self.batching.cost_model().max_weight_per_batch;
+
+        let mut swaps = 0;
+        for group in 0..self.groups.len() {
+            if self.timed_out() {
+                break;
+            }
+
+            let batch = self.batch_of(group);
+            let candidates = self.group_aisles[group]
+                .iter()
+                .flat_map(|aisle| self.aisle_groups[aisle].iter())
+                .map(|other_group| self.batch_of(*other_group))
+                .filter(|other_batch| *other_batch != batch)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .flat_map(|other_batch| self.members[other_batch].iter().copied())
+                .filter(|other_group| {
+                    self.group_aisles[*other_group]
+                        .iter()
+                        .any(|aisle| self.batching.visits_aisle(batch, *aisle))
+                })
+                .collect::<Vec<_>>();
+
+            let volume = self.volume(group);
+            for other_group in candidates {
+                let other_batch = self.batch_of(other_group);
+                let other_volume = self.volume(other_group);
+                if self.batching.volume(batch) + other_volume - volume > max_weight
+                    || self.batching.volume(other_batch) + volume - other_volume > max_weight
+                {
+                    continue;
+                }
+
+                let cost = self.batching.cost();
+                self.move_group(group, other_batch);
+                self.move_group(other_group, batch);
+                if self.batching.cost() < cost {
+                    swaps += 1;
+                    break;
+                }
+                self.move_group(group, batch);
+                self.move_group(other_group, other_batch);
+            }
+        }
+
+        swaps
+    }
+}
@@ -0,0 +1,7 @@
+//! Contains search heuristics besides the genetic algorithm
+pub mod alns;
+pub mod annealing;
+pub mod greedy;
+pub mod local_search;
+pub mod repair;
+pub mod tabu;
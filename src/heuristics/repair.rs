@@ -0,0 +1,245 @@
+//! Repair operators that turn solutions into ones that satisfy stricter constraints
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::ga::batches::{WaiveId, WaiveMapping};
+use crate::ga::orders::{BatchMapping, BatchedArticles};
+use crate::model::*;
+
+/// Orders that cannot share a single waive, because their batches hold too many articles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InseparableOrders {
+    /// Orders whose batches are linked by sharing at least one order
+    pub order_ids: BTreeSet<ID>,
+    /// Number of articles of all these batches
+    pub num_articles: usize,
+    pub max_articles: usize,
+}
+
+impl fmt::Display for InseparableOrders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot keep orders {:?} in a single waive: they need {} articles, but a waive holds \
+             at most {}",
+            self.order_ids, self.num_articles, self.max_articles
+        )
+    }
+}
+
+impl std::error::Error for InseparableOrders {}
+
+/// Checks that every order alone fits into a waive, which any solution without split orders needs
+pub fn check_orders_fit_waives(model: &Model) -> Result<(), InseparableOrders> {
+    let max_articles = model.cost_model().max_articles_per_waive;
+    let mut num_articles: BTreeMap<ID, usize> = BTreeMap::new();
+    for article in model.get_ordered_articles() {
+        *num_articles.entry(article.order_id).or_default() += 1;
+    }
+
+    match num_articles.into_iter().find(|(_, n)| *n > max_articles) {
+        Some((order_id, num_articles)) => Err(InseparableOrders {
+            order_ids: BTreeSet::from([order_id]),
+            num_articles,
+            max_articles,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Merges the waives that share an order, so that no order is split across waives
+///
+/// Batches that share an order form a group, which moves as a whole into the waive that holds most
+/// of its articles, or into the first waive with enough room, or into a new waive. Groups are
+/// placed by decreasing number of articles and never exceed the max articles of a waive. Fails if
+/// a group alone exceeds it.
+pub fn keep_orders_in_waives(
+    model: &Model,
+    batched_articles: &BatchedArticles,
+    waive_mapping: &WaiveMapping,
+) -> Result<WaiveMapping, InseparableOrders> {
+    let max_articles = model.cost_model().max_articles_per_waive;
+    let batches = batched_articles.to_batches();
+
+    let mut groups = linked_batches(batched_articles);
+    let num_articles = |group: &Vec<usize>| {
+        group
+            .iter()
+            .map(|idx| batches[*idx].num_articles())
+            .sum::<usize>()
+    };
+    if let Some(group) = groups
+        .iter()
+        .find(|group| num_articles(group) > max_articles)
+    {
+        return Err(InseparableOrders {
+            order_ids: group
+                .iter()
+                .flat_map(|idx| batches[*idx].order_ids_in_batch())
+                .collect(),
+            num_articles: num_articles(group),
+            max_articles,
+        });
+    }
+    groups.sort_by_key(|group| std::cmp::Reverse(num_articles(group)));
+
+    let mut repaired = vec![0; waive_mapping.len()];
+    // number of articles of every waive, as far as its groups are placed already
+    let mut waives: BTreeMap<WaiveId, usize> = BTreeMap::new();
+    for group in groups.iter() {
+        let group_articles = num_articles(group);
+        let fits = |waive_id: &WaiveId, waives: &BTreeMap<WaiveId, usize>| {
+            waives.get(waive_id).copied().unwrap_or_default() + group_articles <= max_articles
+        };
+
+        // waives that hold articles of the group, with the most articles first
+        let mut shares: BTreeMap<WaiveId, usize> = BTreeMap::new();
+        for idx in group {
+            *shares.entry(waive_mapping[*idx]).or_default() += batches[*idx].num_articles();
+        }
+        let mut shares = shares.into_iter().collect::<Vec<_>>();
+        shares.sort_by_key(|(waive_id, share)| (std::cmp::Reverse(*share), *waive_id));
+
+        let waive_id = shares
+            .iter()
+            .map(|(waive_id, _)| *waive_id)
+            .find(|waive_id| fits(waive_id, &waives))
+            .or_else(|| {
+                waives
+                    .keys()
+                    .copied()
+                    .find(|waive_id| fits(waive_id, &waives))
+            })
+            .unwrap_or_else(|| {
+                (0..)
+                    .find(|waive_id| !waives.contains_key(waive_id))
+                    .expect("there is always an unused waive id")
+            });
+
+        *waives.entry(waive_id).or_default() += group_articles;
+        group.iter().for_each(|idx| repaired[*idx] = waive_id);
+    }
+
+    Ok(repaired)
+}
+
+/// Moves articles out of batches that exceed the max weight, into the fullest batch with enough
+/// room or into a new batch
+///
+/// Articles leave an overweight batch from the largest one on, until the batch fits.
+pub fn repair_overweight_batches(model: &Model, batch_mapping: &BatchMapping) -> BatchMapping {
+    let volumes = model
+        .get_ordered_articles()
+        .iter()
+        .map(|article| article.volume)
+        .collect::<Vec<_>>();
+    let mut batch_mapping = batch_mapping.clone();
+    repair_overflow(
+        &volumes,
+        &mut batch_mapping,
+        model.cost_model().max_weight_per_batch,
+    );
+    batch_mapping
+}
+
+/// Moves batches out of waives that exceed the max articles, into the fullest waive with enough
+/// room or into a new waive
+///
+/// Batches leave an oversized waive from the largest one on, until the waive fits.
+pub fn repair_oversized_waives(
+    model: &Model,
+    batched_articles: &BatchedArticles,
+    waive_mapping: &WaiveMapping,
+) -> WaiveMapping {
+    let num_articles = batched_articles
+        .to_batches()
+        .iter()
+        .map(|batch| batch.num_articles())
+        .collect::<Vec<_>>();
+    let mut waive_mapping = waive_mapping.clone();
+    repair_overflow(
+        &num_articles,
+        &mut waive_mapping,
+        model.cost_model().max_articles_per_waive,
+    );
+    waive_mapping
+}
+
+/// Moves items (by index) out of bins that exceed the capacity, until every bin fits
+///
+/// `bins` maps every item to a bin id. Items leave an overfull bin from the largest one on and go
+/// into the fullest bin with enough room, or into the first empty bin. An item that exceeds the
+/// capacity on its own stays in a bin of its own.
+pub(crate) fn repair_overflow(sizes: &[usize], bins: &mut [usize], capacity: usize) {
+    // at least as many bins as items, so that there is always an empty bin left
+    let num_bins = bins.iter().max().map_or(0, |bin| bin + 1).max(bins.len());
+    let mut loads = vec![0; num_bins];
+    for (size, bin) in sizes.iter().zip(bins.iter()) {
+        loads[*bin] += size;
+    }
+    if loads.iter().all(|load| *load <= capacity) {
+        return;
+    }
+
+    let mut overflowing = (0..bins.len())
+        .filter(|idx| loads[bins[*idx]] > capacity)
+        .collect::<Vec<_>>();
+    overflowing.sort_by_key(|idx| std::cmp::Reverse(sizes[*idx]));
+    let mut removed = Vec::new();
+    for idx in overflowing {
+        if loads[bins[idx]] > capacity {
+            loads[bins[idx]] -= sizes[idx];
+            removed.push(idx);
+        }
+    }
+
+    for idx in removed {
+        let bin = (0..loads.len())
+            .filter(|bin| loads[*bin] > 0 && loads[*bin] + sizes[idx] <= capacity)
+            .max_by_key(|bin| (loads[*bin], std::cmp::Reverse(*bin)))
+            .or_else(|| (0..loads.len()).find(|bin| loads[*bin] == 0))
+            .expect("there is always an empty bin");
+        loads[bin] += sizes[idx];
+        bins[idx] = bin;
+    }
+}
+
+/// Groups the batches (by index) that are linked by sharing orders, directly or via other batches
+fn linked_batches(batched_articles: &BatchedArticles) -> Vec<Vec<usize>> {
+    let batches = batched_articles.to_batches();
+    let mut batches_of_order: BTreeMap<ID, Vec<usize>> = BTreeMap::new();
+    for (idx, batch) in batches.iter().enumerate() {
+        for order_id in batch.order_ids_in_batch() {
+            batches_of_order.entry(order_id).or_default().push(idx);
+        }
+    }
+
+    let mut visited = vec![false; batches.len()];
+    let mut groups = Vec::new();
+    for start in 0..batches.len() {
+        if visited[start] {
+            continue;
+        }
+
+        visited[start] = true;
+        let mut group = vec![start];
+        let mut pos = 0;
+        while pos < group.len() {
+            let idx = group[pos];
+            for order_id in batches[idx].order_ids_in_batch() {
+                for other in batches_of_order[&order_id].iter() {
+                    if !visited[*other] {
+                        visited[*other] = true;
+                        group.push(*other);
+                    }
+                }
+            }
+            pos += 1;
+        }
+
+        group.sort();
+        groups.push(group);
+    }
+
+    groups
+}
@@ -0,0 +1,283 @@
+//! Tabu search over the waives of fixed batches, a replacement for the genetic waive search
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+
+use genevo::random::{Prng, Rng, SeedableRng};
+
+use crate::ga::batches::{WaiveId, WaiveMapping, WaivedBatches};
+use crate::ga::orders::BatchedArticles;
+use crate::heuristics::greedy::greedy_waive_mapping;
+use crate::heuristics::repair::{
+    keep_orders_in_waives, repair_oversized_waives, InseparableOrders,
+};
+use crate::model::*;
+use crate::waiving::Waiving;
+
+/// When a tabu change may be made anyway
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aspiration {
+    /// If it leads to a better solution than the best one so far
+    #[default]
+    BestCost,
+    /// Never
+    None,
+}
+
+/// Parameters of a single tabu search
+#[derive(Debug, Clone, Copy)]
+pub struct TabuParams {
+    /// Max number of iterations, each makes the best allowed change
+    pub iterations: usize,
+    /// Number of iterations during which a batch may not return into the waive it left
+    pub tenure: usize,
+    /// Max number of iterations that get added to the tenure at random, against cycles
+    pub tenure_spread: usize,
+    pub aspiration: Aspiration,
+    /// Whether to try swapping two batches of different waives, besides moving single batches
+    pub swaps: bool,
+    /// Stops the search after this many iterations without a new best solution
+    pub stagnation: Option<usize>,
+    /// Seed for the random part of the tenures
+    pub seed: u64,
+    /// Point in time at which the search stops and returns the best solution found so far
+    pub deadline: Option<Instant>,
+}
+
+impl Default for TabuParams {
+    fn default() -> Self {
+        TabuParams {
+            iterations: 200,
+            tenure: 10,
+            tenure_spread: 5,
+            aspiration: Aspiration::default(),
+            swaps: true,
+            stagnation: Some(50),
+            seed: 0,
+            deadline: None,
+        }
+    }
+}
+
+/// Statistics of a finished tabu search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TabuStats {
+    /// Cost of the start solution, as ranked by the search
+    pub cost_before: usize,
+    /// Cost of the best solution found, as ranked by the search
+    pub cost_after: usize,
+    pub iterations: usize,
+    /// Number of iterations that found a new best solution
+    pub improvements: usize,
+    /// Number of tabu changes made because of the aspiration criterion
+    pub aspirations: usize,
+    /// Whether the time limit stopped the search
+    pub timed_out: bool,
+}
+
+impl fmt::Display for TabuStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cost {} -> {} ({} iterations, {} improvements, {} aspirations)",
+            self.cost_before, self.cost_after, self.iterations, self.improvements, self.aspirations
+        )?;
+        if self.timed_out {
+            write!(f, ", stopped by time limit")?;
+        }
+        Ok(())
+    }
+}
+
+/// A change of the waives of one or two batches
+#[derive(Debug, Clone, Copy)]
+enum Change {
+    Move { idx: usize, to: WaiveId },
+    Swap { idx: usize, other_idx: usize },
+}
+
+/// Ranks waivings by their cost, and equally expensive ones by how full their waives are
+type Rank = (usize, Reverse<usize>);
+
+fn rank(waiving: &Waiving) -> Rank {
+    (waiving.cost(), Reverse(waiving.concentration()))
+}
+
+/// Searches the best waives by a tabu search, starting from the cheapest of the given seeds
+///
+/// Works like [`crate::ga::batches::find_best_waives`]: it minimizes the rest cost of the waives
+/// plus the penalties for split orders and, if `strict`, repairs the result by
+/// [`keep_orders_in_waives`]. Among equally expensive waivings, it prefers those whose articles
+/// gather in fewer waives, which leads the search towards emptying a waive. Without seeds, it
+/// starts from the greedy waives; seeds that exceed the max articles get repaired first.
+pub fn find_best_waives_tabu<'a>(
+    model: &'a Model,
+    batched_articles: &'a BatchedArticles,
+    params: TabuParams,
+    strict: bool,
+    seeds: &[WaiveMapping],
+) -> Result<(WaivedBatches<'a>, TabuStats), InseparableOrders> {
+    let cost_model = model.cost_model();
+    let greedy_seed = [greedy_waive_mapping(model, batched_articles)];
+    let seeds = if seeds.is_empty() {
+        &greedy_seed
+    } else {
+        seeds
+    };
+    let mut waiving = seeds
+        .iter()
+        .map(|seed| {
+            let seed = repair_oversized_waives(model, batched_articles, seed);
+            Waiving::new(cost_model, batched_articles, &seed)
+        })
+        .min_by_key(rank)
+        .expect("at least one seed");
+
+    let mut rng = Prng::seed_from_u64(params.seed);
+    let rank_before = rank(&waiving);
+    let mut best = (waiving.waive_mapping().clone(), rank_before);
+    // iteration until which a batch may not move into a waive, by batch index and waive id
+    let mut tabu_until: BTreeMap<(usize, WaiveId), usize> = BTreeMap::new();
+    let mut stats = TabuStats {
+        cost_before: rank_before.0,
+        cost_after: rank_before.0,
+        iterations: 0,
+        improvements: 0,
+        aspirations: 0,
+        timed_out: false,
+    };
+    let mut last_improvement = 0;
+
+    for iteration in 0..params.iterations {
+        if params
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            stats.timed_out = true;
+            break;
+        }
+        if params
+            .stagnation
+            .is_some_and(|stagnation| iteration - last_improvement >= stagnation)
+        {
+            break;
+        }
+
+        let is_tabu = |idx: usize, waive_id: WaiveId| {
+            tabu_until
+                .get(&(idx, waive_id))
+                .is_some_and(|until| iteration < *until)
+        };
+        let mut chosen: Option<(Rank, Change, bool)> = None;
+        for change in changes(&waiving, params.swaps) {
+            let tabu = match change {
+                Change::Move { idx, to } => is_tabu(idx, to),
+                Change::Swap { idx, other_idx } => {
+                    is_tabu(idx, waiving.waive_of(other_idx))
+                        || is_tabu(other_idx, waiving.waive_of(idx))
+                }
+            };
+
+            let undo = apply(&mut waiving, change);
+            let change_rank = rank(&waiving);
+            apply(&mut waiving, undo);
+
+            let aspirated =
+                tabu && params.aspiration == Aspiration::BestCost && change_rank < best.1;
+            if (!tabu || aspirated)
+                && chosen
+                    .as_ref()
+                    .is_none_or(|(chosen_rank, _, _)| change_rank < *chosen_rank)
+            {
+                chosen = Some((change_rank, change, aspirated));
+            }
+        }
+
+        let Some((change_rank, change, aspirated)) = chosen else {
+            break;
+        };
+        stats.iterations += 1;
+        stats.aspirations += aspirated as usize;
+
+        let undo = apply(&mut waiving, change);
+        let tenure = params.tenure + rng.gen_range(0..=params.tenure_spread);
+        // the batches may not return into the waives they left
+        match undo {
+            Change::Move { idx, to } => {
+                tabu_until.insert((idx, to), iteration + 1 + tenure);
+            }
+            Change::Swap { idx, other_idx } => {
+                tabu_until.insert((idx, waiving.waive_of(other_idx)), iteration + 1 + tenure);
+                tabu_until.insert((other_idx, waiving.waive_of(idx)), iteration + 1 + tenure);
+            }
+        }
+        tabu_until.retain(|_, until| *until > iteration + 1);
+
+        if change_rank < best.1 {
+            best = (waiving.waive_mapping().clone(), change_rank);
+            stats.improvements += 1;
+            last_improvement = iteration + 1;
+        }
+    }
+
+    stats.cost_after = best.1 .0;
+    let waive_mapping = if strict {
+        keep_orders_in_waives(model, batched_articles, &best.0)?
+    } else {
+        best.0
+    };
+
+    Ok((
+        WaivedBatches::from_waive_mapping(waive_mapping, batched_articles),
+        stats,
+    ))
+}
+
+/// All moves and swaps that keep every waive within the max articles
+///
+/// Batches move into every other non-empty waive and, if they share their waive, into an empty
+/// one.
+fn changes(waiving: &Waiving, swaps: bool) -> Vec<Change> {
+    let waive_ids = waiving.non_empty_waive_ids();
+    let empty_waive_id = waiving.empty_waive_id();
+    let mut changes = Vec::new();
+
+    for idx in 0..waiving.num_batches() {
+        let from = waiving.waive_of(idx);
+        for to in waive_ids.iter() {
+            if *to != from && waiving.fits(idx, *to) {
+                changes.push(Change::Move { idx, to: *to });
+            }
+        }
+        if let Some(to) = empty_waive_id {
+            if waiving.waive_articles(from) > waiving.batch_articles(idx) {
+                changes.push(Change::Move { idx, to });
+            }
+        }
+
+        if swaps {
+            for other_idx in idx + 1..waiving.num_batches() {
+                if waiving.waive_of(other_idx) != from && waiving.fits_swapped(idx, other_idx) {
+                    changes.push(Change::Swap { idx, other_idx });
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// Makes the change and returns the change that undoes it
+fn apply(waiving: &mut Waiving, change: Change) -> Change {
+    match change {
+        Change::Move { idx, to } => {
+            let from = waiving.waive_of(idx);
+            waiving.move_batch(idx, to);
+            Change::Move { idx, to: from }
+        }
+        Change::Swap { idx, other_idx } => {
+            waiving.swap_batches(idx, other_idx);
+            change
+        }
+    }
+}
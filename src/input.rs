@@ -1,53 +1,261 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
 use anyhow::{Context, Result};
 use serde::Deserialize;
 use serde_json as json;
 
+use crate::cost::{CostModel, TourModel};
+
 #[derive(Deserialize)]
-pub(crate) struct Input {
+pub struct Input {
     #[serde(rename = "ArticleLocations")]
-    pub(crate) article_locations: Vec<ArticleLocation>,
+    pub article_locations: Vec<ArticleLocation>,
 
     #[serde(rename = "Orders")]
-    pub(crate) orders: Vec<Order>,
+    pub orders: Vec<Order>,
 
     #[serde(rename = "Articles")]
-    pub(crate) articles: Vec<Article>,
+    pub articles: Vec<Article>,
 }
 
 #[derive(Deserialize)]
-pub(crate) struct ArticleLocation {
+pub struct ArticleLocation {
     #[serde(rename = "Warehouse")]
-    pub(crate) warehouse: u16,
+    pub warehouse: u16,
 
     #[serde(rename = "Aisle")]
-    pub(crate) aisle: u16,
+    pub aisle: u16,
 
-    #[allow(dead_code)]
     #[serde(rename = "Position")]
-    pub(crate) position: u16,
+    pub position: u16,
 
     #[serde(rename = "ArticleId")]
-    pub(crate) article_id: u16,
+    pub article_id: u16,
 }
 
 #[derive(Deserialize)]
-pub(crate) struct Order {
+pub struct Order {
     #[serde(rename = "OrderId")]
-    pub(crate) order_id: u16,
+    pub order_id: u16,
     #[serde(rename = "ArticleIds")]
-    pub(crate) article_ids: Vec<u16>,
+    pub article_ids: Vec<u16>,
 }
 
 #[derive(Deserialize)]
-pub(crate) struct Article {
+pub struct Article {
     #[serde(rename = "ArticleId")]
-    pub(crate) article_id: u16,
+    pub article_id: u16,
 
     #[serde(rename = "Volume")]
-    pub(crate) volume: u16,
+    pub volume: u16,
 }
 
-pub(crate) fn load_input(file_path: &str) -> Result<Input> {
+pub fn load_input(file_path: &str) -> Result<Input> {
     let input_file = std::fs::File::open(file_path)?;
     json::from_reader(input_file).context("cannot deserialize input")
 }
+
+impl Input {
+    /// Checks the input for inconsistent or unusable data
+    ///
+    /// Collects all problems instead of stopping at the first one.
+    pub fn validate(&self, cost_model: &CostModel) -> Result<(), InvalidInput> {
+        let mut errors = Vec::new();
+
+        if let Err(error) = cost_model.validate() {
+            errors.push(error);
+        }
+
+        let mut volumes: BTreeMap<u16, u16> = BTreeMap::new();
+        for article in self.articles.iter() {
+            if volumes.insert(article.article_id, article.volume).is_some() {
+                errors.push(InputError::DuplicateArticle {
+                    article_id: article.article_id,
+                });
+            }
+        }
+
+        let mut num_locations: BTreeMap<u16, usize> = BTreeMap::new();
+        for location in self.article_locations.iter() {
+            *num_locations.entry(location.article_id).or_default() += 1;
+            if cost_model.tour_model == TourModel::Distance
+                && location.position as usize >= cost_model.aisle_length
+            {
+                errors.push(InputError::PositionOutsideAisle {
+                    article_id: location.article_id,
+                    position: location.position,
+                    aisle_length: cost_model.aisle_length,
+                });
+            }
+        }
+        for (article_id, num_locations) in num_locations.iter() {
+            if *num_locations > 1 {
+                errors.push(InputError::ManyLocations {
+                    article_id: *article_id,
+                    num_locations: *num_locations,
+                });
+            }
+        }
+
+        if self.orders.is_empty() {
+            errors.push(InputError::NoOrders);
+        }
+        let mut order_ids = BTreeSet::new();
+        let mut ordered_article_ids = BTreeSet::new();
+        for order in self.orders.iter() {
+            if !order_ids.insert(order.order_id) {
+                errors.push(InputError::DuplicateOrder {
+                    order_id: order.order_id,
+                });
+            }
+            if order.article_ids.is_empty() {
+                errors.push(InputError::EmptyOrder {
+                    order_id: order.order_id,
+                });
+            }
+            for article_id in order.article_ids.iter() {
+                if !volumes.contains_key(article_id) {
+                    errors.push(InputError::UnknownArticle {
+                        order_id: order.order_id,
+                        article_id: *article_id,
+                    });
+                }
+                ordered_article_ids.insert(*article_id);
+            }
+        }
+        // articles that no order contains may have any volume, they never get picked
+        for article_id in ordered_article_ids {
+            if !num_locations.contains_key(&article_id) {
+                errors.push(InputError::MissingLocation { article_id });
+            }
+            match volumes.get(&article_id) {
+                Some(0) => errors.push(InputError::ZeroVolume { article_id }),
+                Some(volume) if *volume as usize > cost_model.max_weight_per_batch => {
+                    errors.push(InputError::ExceedsMaxWeight {
+                        article_id,
+                        volume: *volume as usize,
+                        max_weight: cost_model.max_weight_per_batch,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidInput { errors })
+        }
+    }
+}
+
+/// A single problem found by [`Input::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    /// An order contains an article that is not listed in `Articles`
+    UnknownArticle { order_id: u16, article_id: u16 },
+    /// An ordered article is not listed in `ArticleLocations`
+    MissingLocation { article_id: u16 },
+    DuplicateArticle { article_id: u16 },
+    DuplicateOrder { order_id: u16 },
+    ManyLocations { article_id: u16, num_locations: usize },
+    EmptyOrder { order_id: u16 },
+    /// The input does not contain any order, so there is nothing to pick
+    NoOrders,
+    ZeroVolume { article_id: u16 },
+    /// An article is too large to fit into any batch
+    ExceedsMaxWeight {
+        article_id: u16,
+        volume: usize,
+        max_weight: usize,
+    },
+    /// A location lies beyond the aisle length of the distance tour model
+    PositionOutsideAisle {
+        article_id: u16,
+        position: u16,
+        aisle_length: usize,
+    },
+    /// A capacity of the cost model is 0, so that not even a single article fits
+    ZeroCapacity { capacity: &'static str },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::UnknownArticle {
+                order_id,
+                article_id,
+            } => write!(
+                f,
+                "Article {} ordered by order {} but not listed as article",
+                article_id, order_id
+            ),
+            InputError::MissingLocation { article_id } => {
+                write!(f, "Article {} ordered but has no location", article_id)
+            }
+            InputError::DuplicateArticle { article_id } => {
+                write!(f, "Article {} is listed more than once", article_id)
+            }
+            InputError::DuplicateOrder { order_id } => {
+                write!(f, "Order {} is listed more than once", order_id)
+            }
+            InputError::ManyLocations {
+                article_id,
+                num_locations,
+            } => write!(
+                f,
+                "Article {} has {} locations",
+                article_id, num_locations
+            ),
+            InputError::EmptyOrder { order_id } => {
+                write!(f, "Order {} does not contain any article", order_id)
+            }
+            InputError::NoOrders => write!(f, "Input does not contain any order"),
+            InputError::ZeroVolume { article_id } => {
+                write!(f, "Article {} has a volume of 0", article_id)
+            }
+            InputError::ExceedsMaxWeight {
+                article_id,
+                volume,
+                max_weight,
+            } => write!(
+                f,
+                "Article {} has volume {} which exceeds the max weight per batch of {}",
+                article_id, volume, max_weight
+            ),
+            InputError::PositionOutsideAisle {
+                article_id,
+                position,
+                aisle_length,
+            } => write!(
+                f,
+                "Article {} has position {} outside of aisles of length {}",
+                article_id, position, aisle_length
+            ),
+            InputError::ZeroCapacity { capacity } => {
+                write!(f, "{} must be greater than 0", capacity)
+            }
+        }
+    }
+}
+
+impl std::error::Error for InputError {}
+
+/// All problems found by [`Input::validate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidInput {
+    pub errors: Vec<InputError>,
+}
+
+impl fmt::Display for InvalidInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Input contains {} error(s)", self.errors.len())?;
+        for error in self.errors.iter() {
+            write!(f, "\n{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for InvalidInput {}
@@ -0,0 +1,41 @@
+//! Solver for task 'Order Batching' from relaxdays hackathon 2022
+//!
+//! Partitions the ordered articles of an [`Input`] into batches and the batches into waives, so
+//! that the overall picking cost gets minimal.
+//!
+//! ```no_run
+//! use order_batching::{load_input, CostModel, Model, Solver};
+//!
+//! let input = load_input("input1.txt")?;
+//! let model = Model::from_input(&input, CostModel::default())?;
+//! let solution = Solver::new(&model)
+//!     .batch_generations(50)
+//!     .waive_generations(50)
+//!     .solve()?;
+//!
+//! println!("Overall cost {}", solution.cost.overall_cost());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+pub mod batching;
+pub mod bounds;
+pub mod cost;
+pub mod exact;
+pub mod ga;
+pub mod heuristics;
+pub mod input;
+pub mod model;
+pub mod output;
+pub mod routing;
+mod solver;
+pub mod validate;
+pub mod waiving;
+
+pub use cost::{load_cost_model, CostModel, TourModel};
+pub use ga::orders::BatchEncoding;
+pub use ga::Infeasible;
+pub use input::{load_input, Input, InputError, InvalidInput};
+pub use model::Model;
+pub use output::{load_output, Output};
+pub use routing::RoutingPolicy;
+pub use solver::{Algorithm, CostBreakdown, Solution, Solver, WaiveSearch};
+pub use validate::{validate_output, ValidationReport, Violation};
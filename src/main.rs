@@ -1,23 +1,48 @@
-use anyhow::Result;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
 
 mod cli;
-mod ga;
-mod input;
-mod model;
-mod output;
 
 use clap::Parser;
-use ga::orders::*;
-use input::*;
-use model::*;
+use order_batching::exact::{formulate, ExactParams, Formulation};
+use order_batching::ga::{SearchStats, Stagnation};
+use order_batching::heuristics::greedy::{greedy_batches, greedy_waives};
+use order_batching::{load_input, load_output, validate_output, InvalidInput, Model, Solver};
+
+use crate::cli::{Cli, Command, ValidateArgs};
+
+/// Exit code if a validated solution violates any constraint
+const EXIT_INVALID_SOLUTION: u8 = 3;
 
-use crate::{cli::Cli, ga::batches::find_best_waives, output::Output};
+/// Exit code if the input data is inconsistent or unusable
+const EXIT_INVALID_INPUT: u8 = 4;
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let args = Cli::parse();
-    let input = load_input(args.input_file.as_str())?;
 
-    let model = Model::from_input(&input);
+    match args.command {
+        Some(Command::Validate(ref validate_args)) => validate(validate_args),
+        None => solve(&args),
+    }
+}
+
+fn solve(args: &Cli) -> Result<ExitCode> {
+    let input_file = args
+        .input_file
+        .as_deref()
+        .context("No input file given")?;
+    let input = load_input(input_file)?;
+    let cost_model = args.cost_model.cost_model()?;
+
+    let model = match Model::from_input(&input, cost_model) {
+        Ok(model) => model,
+        Err(invalid_input) => return Ok(report_invalid_input(&invalid_input)),
+    };
 
     if cfg!(feature = "info") {
         println!(
@@ -31,44 +56,162 @@ fn main() -> Result<()> {
         );
     }
 
-    let batched_articles = find_best_batches(
-        &model,
-        args.num_batch_individuals,
-        args.num_batch_generations,
-    );
+    if let Some(model_path) = &args.export_model {
+        export_model(&model, model_path)?;
+    }
 
-    let waived_batches = find_best_waives(
-        &model,
-        &batched_articles,
-        args.num_waive_individuals,
-        args.num_waive_generations,
-    );
+    let mut solver = Solver::new(&model)
+        .batch_population(args.num_batch_individuals)
+        .batch_generations(args.num_batch_generations)
+        .waive_population(args.num_waive_individuals)
+        .waive_generations(args.num_waive_generations)
+        .local_search(!args.no_local_search)
+        .strict_waives(args.strict_waives)
+        .infeasible(args.infeasible.into())
+        .algorithm(args.algorithm.into())
+        .batch_encoding(args.batch_encoding.into())
+        .routing_policy(args.routing_policy.into())
+        .threads(args.threads)
+        .migration_interval(args.migration_interval)
+        .annealing(args.annealing.params())
+        .alns(args.alns.params())
+        .waive_search(args.waive_search.into())
+        .tabu(args.tabu.params())
+        .exact(ExactParams {
+            node_limit: Some(args.exact_node_limit),
+            ..ExactParams::default()
+        });
+    if let Some(seed) = args.seed {
+        solver = solver.seed(seed);
+    }
+    if let Some(time_limit_secs) = args.time_limit_secs {
+        let time_limit =
+            Duration::try_from_secs_f64(time_limit_secs).context("Invalid time limit")?;
+        solver = solver.time_limit(time_limit);
+    }
+    if let Some(generations) = args.batch_stagnation_generations {
+        solver = solver.batch_stagnation(Stagnation {
+            generations,
+            epsilon: args.stagnation_epsilon,
+        });
+    }
+    if let Some(generations) = args.waive_stagnation_generations {
+        solver = solver.waive_stagnation(Stagnation {
+            generations,
+            epsilon: args.stagnation_epsilon,
+        });
+    }
+    let solution = solver.solve()?;
 
-    let tour_cost_batches = batched_articles
-        .tour_cost()
-        .expect(format!("Calculated invalid batches {:?}", batched_articles).as_str());
-    let rest_cost_batches = batched_articles.rest_cost();
-    let rest_cost_waives = waived_batches.rest_cost();
-    let overall_cost = tour_cost_batches + rest_cost_batches + rest_cost_waives;
-
-    println!("");
-    println!("[RESULTS]");
-    println!("#Waives {}", waived_batches.to_waives().len());
-    println!("#WBatches {}", batched_articles.to_batches().len());
-    println!("Tour cost {:?}", tour_cost_batches);
-    println!("Rest cost (batches) {:?}", rest_cost_batches);
-    println!("Rest cost (waives) {:?}", rest_cost_waives);
-    println!("");
-    println!("Overall cost {}", overall_cost);
-
-    let output = Output::new(&batched_articles, &waived_batches);
-    if let Some(output_path) = args.output_file {
-        let out_file = std::fs::File::create(&output_path)
-            .expect(format!("Cannot open out file at {}", output_path).as_str());
-        serde_json::to_writer_pretty(out_file, &output)?;
+    println!();
+    println!("[SEARCH]");
+    if let Some(seed) = solution.output.seed {
+        println!("Seed {}", seed);
+    }
+    if let Some(batch_stats) = &solution.batch_stats {
+        print_search_stats("Batches", batch_stats);
+    }
+    if let Some(annealing_stats) = solution.batch_annealing_stats {
+        println!("Batch annealing: {}", annealing_stats);
+    }
+    if let Some(alns_stats) = &solution.alns_stats {
+        println!("ALNS: {}", alns_stats);
+        for operator in alns_stats.destroy.iter().chain(alns_stats.repair.iter()) {
+            println!("  {}", operator);
+        }
+    }
+    if let Some(local_search_stats) = solution.local_search_stats {
+        println!("Local search: {}", local_search_stats);
+    }
+    if let Some(waive_stats) = &solution.waive_stats {
+        print_search_stats("Waives", waive_stats);
+    }
+    if let Some(annealing_stats) = solution.waive_annealing_stats {
+        println!("Waive annealing: {}", annealing_stats);
+    }
+    if let Some(tabu_stats) = solution.tabu_stats {
+        println!("Waive tabu search: {}", tabu_stats);
+    }
+    if let Some(joint_stats) = &solution.joint_stats {
+        print_search_stats("Batches and waives", joint_stats);
+    }
+    if let Some(exact_stats) = solution.exact_stats {
+        println!("Branch and bound: {}", exact_stats);
+    }
+    println!();
+    println!("{}", solution.cost);
+
+    if let Some(output_path) = &args.output_file {
+        let out_file = std::fs::File::create(output_path)
+            .with_context(|| format!("Cannot open out file at {}", output_path))?;
+        serde_json::to_writer_pretty(out_file, &solution.output)?;
     } else if !args.no_output {
-        serde_json::to_writer_pretty(std::io::stdout(), &output)?;
+        serde_json::to_writer_pretty(std::io::stdout(), &solution.output)?;
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate(args: &ValidateArgs) -> Result<ExitCode> {
+    let input = load_input(args.input_file.as_str())?;
+    let output = load_output(args.solution_file.as_str())?;
+    let cost_model = args.cost_model.cost_model()?;
+
+    let model = match Model::from_input(&input, cost_model) {
+        Ok(model) => model,
+        Err(invalid_input) => return Ok(report_invalid_input(&invalid_input)),
+    };
+    let report = validate_output(&model, &output);
+
+    if !report.is_valid() {
+        println!("[VIOLATIONS]");
+        for violation in report.violations.iter() {
+            println!("{}", violation);
+        }
+    }
+
+    println!();
+    println!("{}", report.cost);
+
+    if report.is_valid() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::from(EXIT_INVALID_SOLUTION))
+    }
+}
+
+/// Writes the mixed integer program of the model as LP or MPS file, by the file extension
+///
+/// The program has room for as many batches and waives as the greedy solution allows.
+fn export_model(model: &Model, path: &str) -> Result<()> {
+    let write = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("lp") => Formulation::write_lp,
+        Some("mps") => Formulation::write_mps,
+        _ => bail!("Cannot export the model to {}, expected a .lp or .mps file", path),
+    };
+
+    let batched_articles = greedy_batches(model);
+    let waived_batches = greedy_waives(model, &batched_articles);
+    let formulation = formulate(model, &batched_articles, waived_batches.waive_mapping())?;
+
+    let mut file = BufWriter::new(
+        File::create(path).with_context(|| format!("Cannot open model file at {}", path))?,
+    );
+    write(&formulation, &mut file).with_context(|| format!("Cannot write model to {}", path))
+}
+
+fn print_search_stats(stage: &str, stats: &SearchStats) {
+    println!("{}: {}", stage, stats);
+    for (idx, island) in stats.islands.iter().enumerate() {
+        println!("  Island {}: {}", idx, island);
+    }
+}
+
+fn report_invalid_input(invalid_input: &InvalidInput) -> ExitCode {
+    eprintln!("[INVALID INPUT]");
+    for error in invalid_input.errors.iter() {
+        eprintln!("{}", error);
     }
 
-    Ok(())
+    ExitCode::from(EXIT_INVALID_INPUT)
 }
@@ -3,52 +3,67 @@ use std::{
     iter::repeat,
 };
 
-use crate::input::Input;
+use crate::cost::CostModel;
+use crate::input::{Input, InvalidInput};
 
-pub(crate) const MAX_WEIGHT_PER_BATCH: u16 = 1000; //TODO: convert to usize?
-pub(crate) const MAX_ARTICLES_PER_WAIVE: usize = 250;
-pub(crate) const COST_PER_WAIVE: usize = 10;
-pub(crate) const COST_PER_BATCH: usize = 5;
-pub(crate) const COST_PER_WAREHOUSE: usize = 10;
-pub(crate) const COST_PER_AISLE: usize = 5;
+// Default values of `CostModel`, as given by the original task
+pub const MAX_WEIGHT_PER_BATCH: Volume = 1000;
+pub const MAX_ARTICLES_PER_WAIVE: usize = 250;
+pub const COST_PER_WAIVE: usize = 10;
+pub const COST_PER_BATCH: usize = 5;
+pub const COST_PER_WAREHOUSE: usize = 10;
+pub const COST_PER_AISLE: usize = 5;
 
-pub(crate) type ID = u16;
+pub type ID = u16;
+
+/// Volume of articles and batches
+///
+/// Wide enough to sum up the volumes of all articles of an input without overflowing.
+pub type Volume = usize;
 
 #[derive(Debug)]
-pub(crate) struct Model {
+pub struct Model {
     // articles: Articles,
     orders: Orders,
+    cost_model: CostModel,
 }
 
 impl Model {
-    pub(crate) fn from_input(input: &Input) -> Model {
+    /// Builds the model from an input, which gets validated beforehand
+    pub fn from_input(input: &Input, cost_model: CostModel) -> Result<Model, InvalidInput> {
+        input.validate(&cost_model)?;
+
         let articles = Articles::from_input(input);
         let orders = Orders::from_input(input, &articles);
-        Model { orders }
+        Ok(Model { orders, cost_model })
     }
 
-    pub(crate) fn get_ordered_articles(&self) -> Vec<&OrderedArticle> {
+    pub fn cost_model(&self) -> &CostModel {
+        &self.cost_model
+    }
+
+    pub fn get_ordered_articles(&self) -> Vec<&OrderedArticle> {
         self.orders.ordered_articles()
     }
 
-    pub(crate) fn max_batches_num(&self) -> usize {
+    pub fn max_batches_num(&self) -> usize {
         //TODO: cache result
         self.orders.ordered_articles().len()
     }
 
-    pub(crate) fn max_items_per_batch(&self) -> usize {
+    pub fn max_items_per_batch(&self) -> usize {
         let mut volumes = self
             .get_ordered_articles()
             .iter()
-            .map(|article| article.volume as u16)
-            .collect::<Vec<u16>>();
+            .map(|article| article.volume)
+            .collect::<Vec<Volume>>();
 
         volumes.sort();
 
         let mut sum = 0;
         let mut n = 0;
         for v in volumes {
-            if sum + v > MAX_WEIGHT_PER_BATCH {
+            if sum + v > self.cost_model.max_weight_per_batch {
                 break;
             } else {
                 sum += v;
@@ -59,11 +74,29 @@ impl Model {
         n
     }
 
-    pub(crate) fn num_orders(&self) -> usize {
+    /// Min number of batches that can hold the volume of all ordered articles
+    pub fn min_batches_num(&self) -> usize {
+        let volume = self
+            .get_ordered_articles()
+            .iter()
+            .map(|article| article.volume)
+            .sum::<Volume>();
+
+        volume.div_ceil(self.cost_model.max_weight_per_batch)
+    }
+
+    /// Min number of waives that can hold all ordered articles
+    pub fn min_waives_num(&self) -> usize {
+        self.get_ordered_articles()
+            .len()
+            .div_ceil(self.cost_model.max_articles_per_waive)
+    }
+
+    pub fn num_orders(&self) -> usize {
         self.orders.orders.len()
     }
 
-    pub(crate) fn num_warehouses_of_orders(&self) -> usize {
+    pub fn num_warehouses_of_orders(&self) -> usize {
         self.get_ordered_articles()
             .iter()
             .map(|article| article.location.warehouse)
@@ -71,7 +104,7 @@ impl Model {
             .len()
     }
 
-    pub(crate) fn num_aisles_of_orders(&self) -> usize {
+    pub fn num_aisles_of_orders(&self) -> usize {
         self.get_ordered_articles()
             .iter()
             .map(|article| (article.location.warehouse, article.location.aisle))
@@ -86,50 +119,42 @@ pub(crate) struct Articles {
 }
 
 impl Articles {
+    /// Collects all articles that have a volume and a location
+    ///
+    /// Expects a validated input, see `Input::validate`.
     fn from_input(input: &Input) -> Articles {
-        let mut article_map = BTreeMap::new();
-
-        let ordered_article_ids = input
-            .orders
+        let locations = input
+            .article_locations
             .iter()
-            .flat_map(|order| order.article_ids.iter())
-            .map(|id| *id)
-            .collect::<BTreeSet<_>>();
-
-        let ordered_articles = ordered_article_ids.iter().map(|id| {
-            let volume = input
-                .articles
-                .iter()
-                .find(|article| article.article_id == *id)
-                .map(|article| article.volume as u8)
-                .expect(format!("Article {} ordered but not listed as article", id).as_str());
-
-            let location = input
-                .article_locations
-                .iter()
-                .find(|article_location| article_location.article_id == *id)
-                .map(|article_location| ArticleLocation {
+            .map(|article_location| {
+                let location = ArticleLocation {
                     warehouse: article_location.warehouse,
                     aisle: article_location.aisle,
-                })
-                .expect(format!("Article {} ordered but has no location", id).as_str());
-
-            Article {
-                id: *id,
-                volume,
-                location,
-            }
-        });
+                    position: article_location.position,
+                };
+                (article_location.article_id, location)
+            })
+            .collect::<BTreeMap<_, _>>();
 
-        ordered_articles.for_each(|article| {
-            article_map.insert(article.id, article);
-        });
+        let article_map = input
+            .articles
+            .iter()
+            .filter_map(|article| {
+                let location = locations.get(&article.article_id)?;
+                let article = Article {
+                    id: article.article_id,
+                    volume: article.volume as Volume,
+                    location: *location,
+                };
+                Some((article.id, article))
+            })
+            .collect();
 
         Articles { article_map }
     }
 
-    pub(crate) fn get_article(&self, id: ID) -> &Article {
-        self.article_map.get(&id).unwrap()
+    pub(crate) fn get_article(&self, id: ID) -> Option<&Article> {
+        self.article_map.get(&id)
     }
 
     // pub(crate) fn size(&self) -> usize {
@@ -140,23 +165,23 @@ impl Articles {
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct Article {
     id: u16,
-    volume: u8,
+    volume: Volume,
     location: ArticleLocation,
 }
 
 #[derive(Debug, Clone, Copy)]
-pub(crate) struct ArticleLocation {
-    pub(crate) warehouse: ID,
-    pub(crate) aisle: ID,
-    // position: u16,
+pub struct ArticleLocation {
+    pub warehouse: ID,
+    pub aisle: ID,
+    pub position: ID,
 }
 
 #[derive(Debug)]
-pub(crate) struct OrderedArticle {
-    pub(crate) order_id: ID,
-    pub(crate) id: ID,
-    pub(crate) volume: u8,
-    pub(crate) location: ArticleLocation,
+pub struct OrderedArticle {
+    pub order_id: ID,
+    pub id: ID,
+    pub volume: Volume,
+    pub location: ArticleLocation,
 }
 
 impl OrderedArticle {
@@ -183,9 +208,9 @@ impl Orders {
             .map(|order| {
                 repeat(order.order_id)
                     .zip(order.article_ids.iter())
-                    .map(|(order_id, article_id)| {
-                        let article = articles.get_article(*article_id);
-                        OrderedArticle::new(order_id, *article)
+                    .filter_map(|(order_id, article_id)| {
+                        let article = articles.get_article(*article_id)?;
+                        Some(OrderedArticle::new(order_id, *article))
                     })
                     .collect::<Vec<OrderedArticle>>()
             })
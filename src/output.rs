@@ -1,21 +1,41 @@
-use serde::Serialize;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json as json;
 
+use crate::cost::CostModel;
 use crate::ga::batches::*;
 use crate::ga::orders::*;
 use crate::model::*;
+use crate::routing::{route, RoutingPolicy};
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Output {
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Output {
     #[serde(rename = "Waves")]
-    waves: Vec<Wave>,
+    pub waves: Vec<Wave>,
     #[serde(rename = "Batches")]
-    batches: Vec<Batch>,
+    pub batches: Vec<Batch>,
+    /// Seed of the run that produced this solution
+    #[serde(rename = "Seed", default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Orders that are picked in more than one batch or waive
+    #[serde(rename = "SplitOrders", default, skip_serializing_if = "Option::is_none")]
+    pub split_orders: Option<SplitOrders>,
+    /// Lower bound of the overall cost of any solution
+    #[serde(rename = "LowerBound", default, skip_serializing_if = "Option::is_none")]
+    pub lower_bound: Option<usize>,
+    /// Gap between the overall cost of this solution and the lower bound, relative to the cost
+    #[serde(rename = "Gap", default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<f64>,
 }
 
 impl Output {
-    pub(crate) fn new(
+    /// Builds the output, listing the items of every batch in walking order of the routing policy
+    pub fn new(
         batched_articles: &BatchedArticles,
         waived_batches: &WaivedBatches,
+        cost_model: &CostModel,
+        routing_policy: RoutingPolicy,
+        seed: u64,
     ) -> Output {
         let waves = waived_batches
             .to_waives()
@@ -41,58 +61,95 @@ impl Output {
             .iter()
             .map(|batch| {
                 let batch_id = batch.id as ID;
-                let items = batch
-                    .ordered_articles()
-                    .into_iter()
-                    .map(|article| {
+                let route = route(batch.ordered_articles(), routing_policy, cost_model);
+                let items = route
+                    .articles
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, article)| {
                         let order_id = article.order_id;
                         let article_id = article.id;
                         Item {
                             order_id,
                             article_id,
+                            sequence: idx + 1,
                         }
                     })
                     .collect();
-                let batch_volume = batch.volume() as usize;
+                let batch_volume = batch.volume();
 
                 Batch {
                     batch_id,
                     items,
                     batch_volume,
+                    route_length: route.length,
                 }
             })
             .collect();
 
-        Output { waves, batches }
+        let split_orders = SplitOrders {
+            batches: batched_articles.get_split_orders().into_iter().collect(),
+            waves: waived_batches.get_split_orders().into_iter().collect(),
+        };
+
+        Output {
+            waves,
+            batches,
+            seed: Some(seed),
+            split_orders: Some(split_orders),
+            lower_bound: None,
+            gap: None,
+        }
     }
 }
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Wave {
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SplitOrders {
+    /// Ids of the orders that are picked in more than one batch
+    #[serde(rename = "Batches")]
+    pub batches: Vec<ID>,
+    /// Ids of the orders that are picked in more than one wave
+    #[serde(rename = "Waves")]
+    pub waves: Vec<ID>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Wave {
     #[serde(rename = "WaveId")]
-    wave_id: ID,
+    pub wave_id: ID,
     #[serde(rename = "BatchIds")]
-    batch_ids: Vec<ID>,
+    pub batch_ids: Vec<ID>,
     #[serde(rename = "OrderIds")]
-    order_ids: Vec<ID>,
+    pub order_ids: Vec<ID>,
     #[serde(rename = "WaveSize")]
-    wave_size: usize,
+    pub wave_size: usize,
 }
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Batch {
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Batch {
     #[serde(rename = "BatchId")]
-    batch_id: ID,
+    pub batch_id: ID,
     #[serde(rename = "Items")]
-    items: Vec<Item>,
+    pub items: Vec<Item>,
     #[serde(rename = "BatchVolume")]
-    batch_volume: usize,
+    pub batch_volume: Volume,
+    /// Walking distance of the batch's route, see [`crate::routing::Route`]
+    #[serde(rename = "RouteLength", default)]
+    pub route_length: usize,
 }
 
-#[derive(Serialize, Debug)]
-pub(crate) struct Item {
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Item {
     #[serde(rename = "OrderId")]
-    order_id: ID,
+    pub order_id: ID,
     #[serde(rename = "ArticleId")]
-    article_id: ID,
+    pub article_id: ID,
+    /// Position of the item in the batch's route, starting at 1
+    #[serde(rename = "Sequence", default)]
+    pub sequence: usize,
+}
+
+pub fn load_output(file_path: &str) -> Result<Output> {
+    let output_file = std::fs::File::open(file_path)?;
+    json::from_reader(output_file).context("cannot deserialize output")
 }
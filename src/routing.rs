@@ -0,0 +1,289 @@
+//! Routing policies that order the items of a batch for picking
+//!
+//! Every warehouse is a single block of parallel aisles with a front and a back cross aisle. The
+//! picker starts and ends at the front of aisle 0. Position `p` of an aisle lies `p + 1` steps
+//! away from the front cross aisle, the back cross aisle lies one step behind the last position.
+//! Warehouses are routed one after another, travel between them is not part of the route.
+use std::collections::BTreeMap;
+
+use crate::cost::CostModel;
+use crate::model::*;
+
+/// How the picker walks through the aisles of a warehouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoutingPolicy {
+    /// Enters every visited aisle from the front up to its deepest item and returns
+    ///
+    /// Matches the walking distance of [`crate::TourModel::Distance`].
+    #[default]
+    Return,
+    /// Traverses every visited aisle completely, in alternating directions
+    ///
+    /// If the number of visited aisles is odd, the picker returns in the last one.
+    SShape,
+    /// Traverses the first and the last visited aisle and enters the others from both cross aisles,
+    /// leaving out the largest gap between two items
+    LargestGap,
+    /// Chooses for every visited aisle whether to traverse it or to enter and return, whichever
+    /// makes the whole route shortest
+    OptimalWithinAisle,
+}
+
+/// Items of a batch in walking order
+#[derive(Debug, Clone)]
+pub struct Route<'a> {
+    pub articles: Vec<&'a OrderedArticle>,
+    /// Walking distance, weighted by the cost per position and per aisle step of the cost model
+    pub length: usize,
+}
+
+/// Orders the articles of a batch by warehouse, aisle and position, as given by the policy
+pub fn route<'a>(
+    articles: &[&'a OrderedArticle],
+    policy: RoutingPolicy,
+    cost_model: &CostModel,
+) -> Route<'a> {
+    let mut warehouses: BTreeMap<ID, BTreeMap<ID, Vec<&'a OrderedArticle>>> = BTreeMap::new();
+    articles.iter().for_each(|article| {
+        warehouses
+            .entry(article.location.warehouse)
+            .or_default()
+            .entry(article.location.aisle)
+            .or_default()
+            .push(article)
+    });
+
+    let mut route = Route {
+        articles: Vec::with_capacity(articles.len()),
+        length: 0,
+    };
+    for aisles in warehouses.into_values() {
+        let aisles = aisles
+            .into_iter()
+            .map(|(aisle, mut articles)| {
+                articles.sort_by_key(|article| (article.location.position, article.id));
+                Aisle { aisle, articles }
+            })
+            .collect::<Vec<_>>();
+        // positions beyond the aisle length of the cost model lengthen the aisles
+        let back = aisles
+            .iter()
+            .map(Aisle::max_depth)
+            .max()
+            .unwrap_or_default()
+            .max(cost_model.aisle_length)
+            + 1;
+
+        let warehouse_route = WarehouseRoute::new(&aisles, back, policy);
+        let farthest_aisle = aisles.last().map_or(0, |aisle| aisle.aisle as usize);
+
+        route.articles.extend(warehouse_route.articles);
+        route.length += warehouse_route.positions * cost_model.cost_per_position
+            + 2 * farthest_aisle * cost_model.cost_per_aisle_step;
+    }
+
+    route
+}
+
+/// Articles of a single aisle, sorted by position
+struct Aisle<'a> {
+    aisle: ID,
+    articles: Vec<&'a OrderedArticle>,
+}
+
+impl<'a> Aisle<'a> {
+    fn depth(article: &OrderedArticle) -> usize {
+        article.location.position as usize + 1
+    }
+
+    fn min_depth(&self) -> usize {
+        self.articles
+            .first()
+            .map_or(0, |article| Aisle::depth(article))
+    }
+
+    fn max_depth(&self) -> usize {
+        self.articles
+            .last()
+            .map_or(0, |article| Aisle::depth(article))
+    }
+
+    fn ascending(&self) -> impl Iterator<Item = &'a OrderedArticle> + '_ {
+        self.articles.iter().copied()
+    }
+
+    fn descending(&self) -> impl Iterator<Item = &'a OrderedArticle> + '_ {
+        self.articles.iter().rev().copied()
+    }
+
+    /// Splits the articles at the largest gap, including the gaps to both cross aisles
+    ///
+    /// Returns the number of articles in front of the gap and the size of the gap.
+    fn largest_gap(&self, back: usize) -> (usize, usize) {
+        let depths = std::iter::once(0)
+            .chain(self.articles.iter().map(|article| Aisle::depth(article)))
+            .chain(std::iter::once(back))
+            .collect::<Vec<_>>();
+
+        depths
+            .windows(2)
+            .enumerate()
+            .map(|(idx, pair)| (idx, pair[1] - pair[0]))
+            // the first of equally large gaps
+            .fold((0, 0), |best, gap| if gap.1 > best.1 { gap } else { best })
+    }
+}
+
+/// Cross aisle the picker stands at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Front,
+    Back,
+}
+
+/// Ways to pick the articles of one aisle, for [`RoutingPolicy::OptimalWithinAisle`]
+#[derive(Debug, Clone, Copy)]
+enum AisleMove {
+    ReturnToFront,
+    ReturnToBack,
+    TraverseToBack,
+    TraverseToFront,
+}
+
+/// Route through a single warehouse, without the travel along the cross aisles
+struct WarehouseRoute<'a> {
+    articles: Vec<&'a OrderedArticle>,
+    /// Number of positions walked inside the aisles
+    positions: usize,
+}
+
+impl<'a> WarehouseRoute<'a> {
+    fn new(aisles: &[Aisle<'a>], back: usize, policy: RoutingPolicy) -> WarehouseRoute<'a> {
+        match policy {
+            RoutingPolicy::Return => WarehouseRoute::return_route(aisles),
+            RoutingPolicy::SShape => WarehouseRoute::s_shape(aisles, back),
+            RoutingPolicy::LargestGap => WarehouseRoute::largest_gap(aisles, back),
+            RoutingPolicy::OptimalWithinAisle => WarehouseRoute::optimal_within_aisle(aisles, back),
+        }
+    }
+
+    fn return_route(aisles: &[Aisle<'a>]) -> WarehouseRoute<'a> {
+        WarehouseRoute {
+            articles: aisles.iter().flat_map(Aisle::ascending).collect(),
+            positions: aisles.iter().map(|aisle| 2 * aisle.max_depth()).sum(),
+        }
+    }
+
+    fn s_shape(aisles: &[Aisle<'a>], back: usize) -> WarehouseRoute<'a> {
+        let mut articles = Vec::new();
+        let mut positions = 0;
+
+        for (idx, aisle) in aisles.iter().enumerate() {
+            if idx + 1 == aisles.len() && idx % 2 == 0 {
+                articles.extend(aisle.ascending());
+                positions += 2 * aisle.max_depth();
+            } else if idx % 2 == 0 {
+                articles.extend(aisle.ascending());
+                positions += back;
+            } else {
+                articles.extend(aisle.descending());
+                positions += back;
+            }
+        }
+
+        WarehouseRoute {
+            articles,
+            positions,
+        }
+    }
+
+    fn largest_gap(aisles: &[Aisle<'a>], back: usize) -> WarehouseRoute<'a> {
+        let (first, last) = match aisles {
+            [] | [_] => return WarehouseRoute::return_route(aisles),
+            [first, .., last] => (first, last),
+        };
+        let middle = &aisles[1..aisles.len() - 1];
+        let gaps = middle
+            .iter()
+            .map(|aisle| aisle.largest_gap(back))
+            .collect::<Vec<_>>();
+
+        let mut articles = Vec::new();
+        articles.extend(first.ascending());
+        // along the back cross aisle, picking the back parts of the middle aisles
+        for (aisle, (split, _)) in middle.iter().zip(gaps.iter()) {
+            articles.extend(aisle.articles[*split..].iter().rev().copied());
+        }
+        articles.extend(last.descending());
+        // along the front cross aisle, picking the front parts of the middle aisles
+        for (aisle, (split, _)) in middle.iter().zip(gaps.iter()).rev() {
+            articles.extend(aisle.articles[..*split].iter().copied());
+        }
+
+        let positions = 2 * back + gaps.iter().map(|(_, gap)| 2 * (back - gap)).sum::<usize>();
+
+        WarehouseRoute {
+            articles,
+            positions,
+        }
+    }
+
+    /// Finds the shortest sequence of aisle moves that starts and ends at the front cross aisle
+    fn optimal_within_aisle(aisles: &[Aisle<'a>], back: usize) -> WarehouseRoute<'a> {
+        // shortest walk to each side after the visited aisles, with the moves that lead there
+        let mut front: Option<(usize, Vec<AisleMove>)> = Some((0, Vec::new()));
+        let mut behind: Option<(usize, Vec<AisleMove>)> = None;
+
+        for aisle in aisles {
+            let options = |side: Side| {
+                let from_front = front.as_ref().map(|(positions, moves)| match side {
+                    Side::Front => (
+                        positions + 2 * aisle.max_depth(),
+                        moves,
+                        AisleMove::ReturnToFront,
+                    ),
+                    Side::Back => (positions + back, moves, AisleMove::TraverseToBack),
+                });
+                let from_back = behind.as_ref().map(|(positions, moves)| match side {
+                    Side::Front => (positions + back, moves, AisleMove::TraverseToFront),
+                    Side::Back => (
+                        positions + 2 * (back - aisle.min_depth()),
+                        moves,
+                        AisleMove::ReturnToBack,
+                    ),
+                });
+
+                [from_front, from_back]
+                    .into_iter()
+                    .flatten()
+                    .min_by_key(|(positions, _, _)| *positions)
+                    .map(|(positions, moves, aisle_move)| {
+                        let mut moves = moves.clone();
+                        moves.push(aisle_move);
+                        (positions, moves)
+                    })
+            };
+
+            (front, behind) = (options(Side::Front), options(Side::Back));
+        }
+
+        let (positions, moves) = front.expect("front cross aisle is always reachable");
+        let articles = aisles
+            .iter()
+            .zip(moves)
+            .flat_map(|(aisle, aisle_move)| match aisle_move {
+                AisleMove::ReturnToFront | AisleMove::TraverseToBack => {
+                    aisle.ascending().collect::<Vec<_>>()
+                }
+                AisleMove::ReturnToBack | AisleMove::TraverseToFront => {
+                    aisle.descending().collect::<Vec<_>>()
+                }
+            })
+            .collect();
+
+        WarehouseRoute {
+            articles,
+            positions,
+        }
+    }
+}
@@ -0,0 +1,698 @@
+//! Builder-style entry point that runs both search stages
+//!
+//! The solver owns the seeds and deadlines of all searches: it draws their seeds from
+//! [`Solver::seed`] and derives their deadlines from [`Solver::time_limit`], overriding those of
+//! the parameters given for a single search.
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use genevo::random::{Prng, Rng, SeedableRng};
+
+use crate::bounds::LowerBound;
+use crate::cost::TourModel;
+use crate::exact::{solve_exact, ExactParams, ExactStats};
+use crate::ga::batches::{find_best_waives, WaiveMapping, WaivedBatches};
+use crate::ga::joint::{find_best_joint, joint_mapping};
+use crate::ga::orders::{article_groups, find_best_batches, BatchEncoding, BatchedArticles};
+use crate::ga::{GaParams, Infeasible, Islands, SearchStats, Stagnation, MIN_POPULATION_SIZE};
+use crate::heuristics::alns::{alns_batches, AlnsParams, AlnsStats};
+use crate::heuristics::annealing::{anneal_batches, anneal_waives, SaParams, SaStats};
+use crate::heuristics::greedy::{
+    greedy_batch_mapping, greedy_encoded_batches, greedy_waive_mapping,
+};
+use crate::heuristics::local_search::{improve_batches, LocalSearchStats};
+use crate::heuristics::repair::{check_orders_fit_waives, keep_orders_in_waives};
+use crate::heuristics::tabu::{find_best_waives_tabu, TabuParams, TabuStats};
+use crate::model::Model;
+use crate::output::Output;
+use crate::routing::RoutingPolicy;
+
+/// Share of the time limit that is reserved for the orders<->batches stage
+///
+/// The batches<->waives stage gets the rest, including any time the first stage did not use.
+const BATCH_STAGE_TIME_SHARE: f64 = 0.75;
+
+/// Strategy to search batches and waives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    /// Genetic search, whose initial populations contain the greedy solutions
+    #[default]
+    Ga,
+    /// Greedy construction only, much faster but usually more expensive
+    Greedy,
+    /// Single genetic search over batches and waives together, which optimizes the overall cost
+    /// instead of the cost of each stage
+    Joint,
+    /// Simulated annealing, first over the greedy batches and then over the greedy waives
+    Sa,
+    /// Adaptive large neighborhood search over the greedy batches, then greedy waives
+    Alns,
+    /// Branch and bound over a mixed integer program, starting from the greedy solution improved
+    /// by the local search
+    ///
+    /// Only suits models with the counting tour model and no penalties for split orders. Proves
+    /// optimality for tiny models only; larger ones stop at the node limit with a gap.
+    Exact,
+}
+
+/// Search for the waives of the found batches, in the second stage of [`Algorithm::Ga`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaiveSearch {
+    /// Genetic search, whose initial population contains the greedy waives
+    #[default]
+    Ga,
+    /// Tabu search, starting from the greedy waives
+    Tabu,
+}
+
+/// Configures and runs the search for batches and waives on a [`Model`]
+#[derive(Debug, Clone, Copy)]
+pub struct Solver<'a> {
+    model: &'a Model,
+    num_batch_individuals: usize,
+    num_batch_generations: usize,
+    num_waive_individuals: usize,
+    num_waive_generations: usize,
+    seed: Option<u64>,
+    time_limit: Option<Duration>,
+    batch_stagnation: Option<Stagnation>,
+    waive_stagnation: Option<Stagnation>,
+    local_search: bool,
+    algorithm: Algorithm,
+    batch_encoding: BatchEncoding,
+    strict_waives: bool,
+    infeasible: Infeasible,
+    routing_policy: RoutingPolicy,
+    threads: usize,
+    migration_interval: usize,
+    annealing: SaParams,
+    alns: AlnsParams,
+    waive_search: WaiveSearch,
+    tabu: TabuParams,
+    exact: ExactParams,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(model: &'a Model) -> Solver<'a> {
+        Solver {
+            model,
+            num_batch_individuals: 100,
+            num_batch_generations: 100,
+            num_waive_individuals: 100,
+            num_waive_generations: 100,
+            seed: None,
+            time_limit: None,
+            batch_stagnation: None,
+            waive_stagnation: None,
+            local_search: true,
+            algorithm: Algorithm::default(),
+            batch_encoding: BatchEncoding::default(),
+            strict_waives: false,
+            infeasible: Infeasible::default(),
+            routing_policy: RoutingPolicy::default(),
+            threads: 1,
+            migration_interval: 10,
+            annealing: SaParams::default(),
+            alns: AlnsParams::default(),
+            waive_search: WaiveSearch::default(),
+            tabu: TabuParams::default(),
+            exact: ExactParams::default(),
+        }
+    }
+
+    /// Initial size of orders<->batches population, at least [`MIN_POPULATION_SIZE`]
+    pub fn batch_population(mut self, num_individuals: usize) -> Solver<'a> {
+        self.num_batch_individuals = num_individuals;
+        self
+    }
+
+    /// Max number of generations for orders<->batches
+    pub fn batch_generations(mut self, num_generations: usize) -> Solver<'a> {
+        self.num_batch_generations = num_generations;
+        self
+    }
+
+    /// Initial size of batches<->waives population, at least [`MIN_POPULATION_SIZE`]
+    pub fn waive_population(mut self, num_individuals: usize) -> Solver<'a> {
+        self.num_waive_individuals = num_individuals;
+        self
+    }
+
+    /// Max number of generations for batches<->waives
+    pub fn waive_generations(mut self, num_generations: usize) -> Solver<'a> {
+        self.num_waive_generations = num_generations;
+        self
+    }
+
+    /// Seed for all random decisions, which makes runs reproducible
+    ///
+    /// If no seed is given, a random one is used. Either way, the seed is recorded in the output.
+    pub fn seed(mut self, seed: u64) -> Solver<'a> {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Max wall-clock time for both search stages together
+    ///
+    /// When the time is up, the best solution found so far is returned. Generation limits still
+    /// apply.
+    pub fn time_limit(mut self, time_limit: Duration) -> Solver<'a> {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Stops the orders<->batches stage early if its best fitness does not improve anymore
+    pub fn batch_stagnation(mut self, stagnation: Stagnation) -> Solver<'a> {
+        self.batch_stagnation = Some(stagnation);
+        self
+    }
+
+    /// Stops the batches<->waives stage early if its best fitness does not improve anymore
+    pub fn waive_stagnation(mut self, stagnation: Stagnation) -> Solver<'a> {
+        self.waive_stagnation = Some(stagnation);
+        self
+    }
+
+    /// Whether to improve the found batches by a local search before assigning them to waives
+    ///
+    /// Enabled by default.
+    pub fn local_search(mut self, local_search: bool) -> Solver<'a> {
+        self.local_search = local_search;
+        self
+    }
+
+    /// Strategy to search batches and waives, defaults to [`Algorithm::Ga`]
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Solver<'a> {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// What a gene of the orders<->batches search stands for, defaults to
+    /// [`BatchEncoding::Articles`]
+    ///
+    /// Also applies to the greedy batches, the local search and the batch part of
+    /// [`Algorithm::Joint`].
+    pub fn batch_encoding(mut self, batch_encoding: BatchEncoding) -> Solver<'a> {
+        self.batch_encoding = batch_encoding;
+        self
+    }
+
+    /// Whether to keep the batches of every order in a single waive, disabled by default
+    ///
+    /// Waives that share an order get merged, see [`keep_orders_in_waives`]. Solving fails if the
+    /// batches of some orders do not fit into a single waive.
+    pub fn strict_waives(mut self, strict_waives: bool) -> Solver<'a> {
+        self.strict_waives = strict_waives;
+        self
+    }
+
+    /// How the genetic searches deal with individuals that exceed a max weight or max articles,
+    /// defaults to [`Infeasible::Repair`]
+    pub fn infeasible(mut self, infeasible: Infeasible) -> Solver<'a> {
+        self.infeasible = infeasible;
+        self
+    }
+
+    /// Order of the items of each batch in the output, defaults to [`RoutingPolicy::Return`]
+    pub fn routing_policy(mut self, routing_policy: RoutingPolicy) -> Solver<'a> {
+        self.routing_policy = routing_policy;
+        self
+    }
+
+    /// Number of threads, each evolving its own population of every genetic search
+    ///
+    /// With more than one thread, the populations form an island model and exchange their best
+    /// individuals every [`Solver::migration_interval`] generations. Defaults to 1.
+    pub fn threads(mut self, threads: usize) -> Solver<'a> {
+        self.threads = threads;
+        self
+    }
+
+    /// Number of generations between two migrations of the island model, defaults to 10
+    pub fn migration_interval(mut self, migration_interval: usize) -> Solver<'a> {
+        self.migration_interval = migration_interval;
+        self
+    }
+
+    /// Cooling schedule and moves of [`Algorithm::Sa`], shared by the batch and the waive stage
+    pub fn annealing(mut self, annealing: SaParams) -> Solver<'a> {
+        self.annealing = annealing;
+        self
+    }
+
+    /// Iterations, destroy size, weight adaption and acceptance of [`Algorithm::Alns`]
+    ///
+    /// The search only improves the batches, the waives are packed greedily afterwards.
+    pub fn alns(mut self, alns: AlnsParams) -> Solver<'a> {
+        self.alns = alns;
+        self
+    }
+
+    /// Search for the waives in the second stage of [`Algorithm::Ga`], defaults to
+    /// [`WaiveSearch::Ga`]
+    pub fn waive_search(mut self, waive_search: WaiveSearch) -> Solver<'a> {
+        self.waive_search = waive_search;
+        self
+    }
+
+    /// Iterations, tenure and aspiration of [`WaiveSearch::Tabu`]
+    ///
+    /// The search starts from the greedy waives of the batches found in the first stage.
+    pub fn tabu(mut self, tabu: TabuParams) -> Solver<'a> {
+        self.tabu = tabu;
+        self
+    }
+
+    /// Node limit of [`Algorithm::Exact`]
+    pub fn exact(mut self, exact: ExactParams) -> Solver<'a> {
+        self.exact = exact;
+        self
+    }
+
+    /// Runs the search and returns the best solution found
+    ///
+    /// Fails if a population is smaller than [`MIN_POPULATION_SIZE`], even if the algorithm does
+    /// not use it.
+    pub fn solve(&self) -> Result<Solution> {
+        for (stage, num_individuals) in [
+            ("orders<->batches", self.num_batch_individuals),
+            ("batches<->waives", self.num_waive_individuals),
+        ] {
+            if num_individuals < MIN_POPULATION_SIZE {
+                bail!(
+                    "The {} population has {} individuals, but needs at least {}",
+                    stage,
+                    num_individuals,
+                    MIN_POPULATION_SIZE
+                );
+            }
+        }
+
+        let seed = self
+            .seed
+            .unwrap_or_else(|| Prng::from_entropy().gen::<u64>());
+        let mut rng = Prng::seed_from_u64(seed);
+        let started_at = Instant::now();
+
+        if self.strict_waives {
+            check_orders_fit_waives(self.model)?;
+        }
+
+        let mut stats = Stats::default();
+        let (batched_articles, waive_mapping) = match self.algorithm {
+            Algorithm::Ga | Algorithm::Greedy | Algorithm::Sa | Algorithm::Alns => {
+                self.solve_in_stages(&mut rng, started_at, &mut stats)?
+            }
+            Algorithm::Joint => self.solve_jointly(&mut rng, started_at, &mut stats)?,
+            Algorithm::Exact => self.solve_exactly(started_at, &mut stats)?,
+        };
+        let waived_batches = WaivedBatches::from_waive_mapping(waive_mapping, &batched_articles);
+
+        let mut cost = CostBreakdown::new(self.model, &batched_articles, &waived_batches)?;
+        if let Some(exact_stats) = stats.exact {
+            cost.lower_bound = cost.lower_bound.max(exact_stats.bound);
+        }
+        let mut output = Output::new(
+            &batched_articles,
+            &waived_batches,
+            self.model.cost_model(),
+            self.routing_policy,
+            seed,
+        );
+        output.lower_bound = Some(cost.lower_bound);
+        output.gap = Some(cost.gap());
+
+        Ok(Solution {
+            output,
+            cost,
+            batch_stats: stats.batch,
+            local_search_stats: stats.local_search,
+            waive_stats: stats.waive,
+            joint_stats: stats.joint,
+            batch_annealing_stats: stats.batch_annealing,
+            waive_annealing_stats: stats.waive_annealing,
+            alns_stats: stats.alns,
+            tabu_stats: stats.tabu,
+            exact_stats: stats.exact,
+        })
+    }
+
+    /// Searches the batches first and then assigns the found batches to waives
+    fn solve_in_stages(
+        &self,
+        rng: &mut Prng,
+        started_at: Instant,
+        stats: &mut Stats,
+    ) -> Result<(BatchedArticles<'a>, WaiveMapping)> {
+        let batch_deadline = self
+            .time_limit
+            .map(|time_limit| started_at + time_limit.mul_f64(BATCH_STAGE_TIME_SHARE));
+        let waive_deadline = self.time_limit.map(|time_limit| started_at + time_limit);
+
+        let batched_articles = match self.algorithm {
+            Algorithm::Greedy => greedy_encoded_batches(self.model, self.batch_encoding),
+            Algorithm::Sa => {
+                let (batched_articles, annealing_stats) = anneal_batches(
+                    self.model,
+                    &greedy_encoded_batches(self.model, self.batch_encoding),
+                    self.batch_encoding,
+                    &SaParams {
+                        seed: rng.gen(),
+                        deadline: batch_deadline,
+                        ..self.annealing
+                    },
+                );
+                stats.batch_annealing = Some(annealing_stats);
+                batched_articles
+            }
+            Algorithm::Alns => {
+                let (batched_articles, alns_stats) = alns_batches(
+                    self.model,
+                    &greedy_encoded_batches(self.model, self.batch_encoding),
+                    self.batch_encoding,
+                    &AlnsParams {
+                        seed: rng.gen(),
+                        deadline: batch_deadline,
+                        ..self.alns
+                    },
+                );
+                stats.alns = Some(alns_stats);
+                batched_articles
+            }
+            _ => {
+                let (batched_articles, batch_stats) = find_best_batches(
+                    self.model,
+                    GaParams {
+                        num_individuals: self.num_batch_individuals,
+                        num_generations: self.num_batch_generations,
+                        seed: rng.gen(),
+                        deadline: batch_deadline,
+                        stagnation: self.batch_stagnation,
+                        islands: self.islands(),
+                        infeasible: self.infeasible,
+                    },
+                    self.batch_encoding,
+                    &[greedy_batch_mapping(self.model, self.batch_encoding)],
+                )?;
+                stats.batch = Some(batch_stats);
+                batched_articles
+            }
+        };
+
+        let batched_articles = if self.local_search {
+            let (batched_articles, local_search_stats) = improve_batches(
+                self.model,
+                &batched_articles,
+                self.batch_encoding,
+                batch_deadline,
+            );
+            stats.local_search = Some(local_search_stats);
+            batched_articles
+        } else {
+            batched_articles
+        };
+
+        // fails early if the batches link orders that do not fit into a single waive
+        let greedy_waive_mapping = greedy_waive_mapping(self.model, &batched_articles);
+        let greedy_waive_mapping = if self.strict_waives {
+            keep_orders_in_waives(self.model, &batched_articles, &greedy_waive_mapping)?
+        } else {
+            greedy_waive_mapping
+        };
+
+        let waive_mapping = match self.algorithm {
+            Algorithm::Greedy | Algorithm::Alns => greedy_waive_mapping,
+            Algorithm::Sa => {
+                let (waived_batches, annealing_stats) = anneal_waives(
+                    self.model,
+                    &batched_articles,
+                    &greedy_waive_mapping,
+                    &SaParams {
+                        seed: rng.gen(),
+                        deadline: waive_deadline,
+                        ..self.annealing
+                    },
+                );
+                stats.waive_annealing = Some(annealing_stats);
+                if self.strict_waives {
+                    keep_orders_in_waives(
+                        self.model,
+                        &batched_articles,
+                        waived_batches.waive_mapping(),
+                    )?
+                } else {
+                    waived_batches.waive_mapping().clone()
+                }
+            }
+            _ if self.waive_search == WaiveSearch::Tabu => {
+                let (waived_batches, tabu_stats) = find_best_waives_tabu(
+                    self.model,
+                    &batched_articles,
+                    TabuParams {
+                        seed: rng.gen(),
+                        deadline: waive_deadline,
+                        ..self.tabu
+                    },
+                    self.strict_waives,
+                    &[greedy_waive_mapping],
+                )?;
+                stats.tabu = Some(tabu_stats);
+                waived_batches.waive_mapping().clone()
+            }
+            _ => {
+                let (waived_batches, waive_stats) = find_best_waives(
+                    self.model,
+                    &batched_articles,
+                    GaParams {
+                        num_individuals: self.num_waive_individuals,
+                        num_generations: self.num_waive_generations,
+                        seed: rng.gen(),
+                        deadline: waive_deadline,
+                        stagnation: self.waive_stagnation,
+                        islands: self.islands(),
+                        infeasible: self.infeasible,
+                    },
+                    self.strict_waives,
+                    &[greedy_waive_mapping],
+                )?;
+                stats.waive = Some(waive_stats);
+                waived_batches.waive_mapping().clone()
+            }
+        };
+
+        Ok((batched_articles, waive_mapping))
+    }
+
+    /// Searches batches and waives in a single genetic search, seeded with the greedy solution
+    ///
+    /// Uses the population, generations and stagnation settings of the orders<->batches stage and
+    /// the whole time limit. The local search does not apply, as it would break the waives.
+    fn solve_jointly(
+        &self,
+        rng: &mut Prng,
+        started_at: Instant,
+        stats: &mut Stats,
+    ) -> Result<(BatchedArticles<'a>, WaiveMapping)> {
+        let greedy_batched_articles = greedy_encoded_batches(self.model, self.batch_encoding);
+        let greedy_seed = joint_mapping(
+            &article_groups(self.model, self.batch_encoding),
+            &greedy_batched_articles,
+            &greedy_waive_mapping(self.model, &greedy_batched_articles),
+        );
+
+        let (batched_articles, waive_mapping, joint_stats) = find_best_joint(
+            self.model,
+            GaParams {
+                num_individuals: self.num_batch_individuals,
+                num_generations: self.num_batch_generations,
+                seed: rng.gen(),
+                deadline: self.time_limit.map(|time_limit| started_at + time_limit),
+                stagnation: self.batch_stagnation,
+                islands: self.islands(),
+                infeasible: self.infeasible,
+            },
+            self.batch_encoding,
+            self.strict_waives,
+            &[greedy_seed],
+        )?;
+        stats.joint = Some(joint_stats);
+
+        Ok((batched_articles, waive_mapping))
+    }
+
+    /// Searches the cheapest batches and waives by branch and bound, starting from the greedy
+    /// solution improved by the local search, within the whole time limit
+    ///
+    /// The branch and bound ignores the batch encoding; with strict waives, the found waives get
+    /// repaired afterwards.
+    fn solve_exactly(
+        &self,
+        started_at: Instant,
+        stats: &mut Stats,
+    ) -> Result<(BatchedArticles<'a>, WaiveMapping)> {
+        let deadline = self.time_limit.map(|time_limit| started_at + time_limit);
+        let greedy_batched_articles = greedy_encoded_batches(self.model, self.batch_encoding);
+        let start_batched_articles = if self.local_search {
+            let (batched_articles, local_search_stats) = improve_batches(
+                self.model,
+                &greedy_batched_articles,
+                self.batch_encoding,
+                deadline,
+            );
+            stats.local_search = Some(local_search_stats);
+            batched_articles
+        } else {
+            greedy_batched_articles
+        };
+
+        let (batched_articles, waive_mapping, exact_stats) = solve_exact(
+            self.model,
+            &start_batched_articles,
+            &greedy_waive_mapping(self.model, &start_batched_articles),
+            ExactParams {
+                deadline,
+                ..self.exact
+            },
+        )?;
+        stats.exact = Some(exact_stats);
+
+        let waive_mapping = if self.strict_waives {
+            keep_orders_in_waives(self.model, &batched_articles, &waive_mapping)?
+        } else {
+            waive_mapping
+        };
+        Ok((batched_articles, waive_mapping))
+    }
+}
+
+impl<'a> Solver<'a> {
+    fn islands(&self) -> Option<Islands> {
+        (self.threads > 1).then_some(Islands {
+            islands: self.threads,
+            migration_interval: self.migration_interval,
+        })
+    }
+}
+
+/// Statistics of the searches that ran
+#[derive(Debug, Default)]
+struct Stats {
+    batch: Option<SearchStats>,
+    local_search: Option<LocalSearchStats>,
+    waive: Option<SearchStats>,
+    joint: Option<SearchStats>,
+    batch_annealing: Option<SaStats>,
+    waive_annealing: Option<SaStats>,
+    alns: Option<AlnsStats>,
+    tabu: Option<TabuStats>,
+    exact: Option<ExactStats>,
+}
+
+/// An owned result of [`Solver::solve`]
+#[derive(Debug)]
+pub struct Solution {
+    pub output: Output,
+    pub cost: CostBreakdown,
+    /// Statistics of the genetic orders<->batches stage, if it ran
+    pub batch_stats: Option<SearchStats>,
+    /// Statistics of the local search on the found batches, if enabled
+    pub local_search_stats: Option<LocalSearchStats>,
+    /// Statistics of the genetic batches<->waives stage, if it ran
+    pub waive_stats: Option<SearchStats>,
+    /// Statistics of the joint genetic search of batches and waives, if it ran
+    pub joint_stats: Option<SearchStats>,
+    /// Statistics of the simulated annealing of the batches, if it ran
+    pub batch_annealing_stats: Option<SaStats>,
+    /// Statistics of the simulated annealing of the waives, if it ran
+    pub waive_annealing_stats: Option<SaStats>,
+    /// Statistics of the adaptive large neighborhood search of the batches, if it ran
+    pub alns_stats: Option<AlnsStats>,
+    /// Statistics of the tabu search of the waives, if it ran
+    pub tabu_stats: Option<TabuStats>,
+    /// Statistics of the branch and bound, if it ran
+    pub exact_stats: Option<ExactStats>,
+}
+
+/// The costs of a solution, split by their origin
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostBreakdown {
+    pub tour_model: TourModel,
+    pub num_waives: usize,
+    pub num_batches: usize,
+    /// Number of orders whose articles are picked in more than one batch
+    pub num_split_orders: usize,
+    /// Number of orders whose articles are picked in more than one waive
+    pub num_waive_split_orders: usize,
+    pub tour_cost: usize,
+    pub rest_cost_batches: usize,
+    pub rest_cost_waives: usize,
+    /// Penalties for the orders split across batches and waives
+    pub split_cost: usize,
+    /// Lower bound of the overall cost of any solution, see [`LowerBound`]
+    pub lower_bound: usize,
+}
+
+impl CostBreakdown {
+    pub fn new(
+        model: &Model,
+        batched_articles: &BatchedArticles,
+        waived_batches: &WaivedBatches,
+    ) -> Result<CostBreakdown> {
+        let cost_model = model.cost_model();
+        let tour_cost = batched_articles
+            .tour_cost(cost_model)
+            .ok_or_else(|| anyhow!("Calculated invalid batches {:?}", batched_articles))?;
+
+        Ok(CostBreakdown {
+            tour_model: cost_model.tour_model,
+            num_waives: waived_batches.to_waives().len(),
+            num_batches: batched_articles.to_batches().len(),
+            num_split_orders: batched_articles.get_split_orders().len(),
+            num_waive_split_orders: waived_batches.get_split_orders().len(),
+            tour_cost,
+            rest_cost_batches: batched_articles.rest_cost(cost_model),
+            rest_cost_waives: waived_batches.rest_cost(cost_model),
+            split_cost: batched_articles.split_cost(cost_model)
+                + waived_batches.split_cost(cost_model),
+            lower_bound: LowerBound::new(model).overall_cost(),
+        })
+    }
+
+    pub fn overall_cost(&self) -> usize {
+        self.tour_cost + self.rest_cost_batches + self.rest_cost_waives + self.split_cost
+    }
+
+    /// Gap between the overall cost and the lower bound, relative to the overall cost
+    ///
+    /// The solution is at most this much more expensive than an optimal one.
+    pub fn gap(&self) -> f64 {
+        let cost = self.overall_cost();
+        if cost == 0 {
+            0.0
+        } else {
+            cost.saturating_sub(self.lower_bound) as f64 / cost as f64
+        }
+    }
+}
+
+impl fmt::Display for CostBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[RESULTS]")?;
+        writeln!(f, "#Waives {}", self.num_waives)?;
+        writeln!(f, "#WBatches {}", self.num_batches)?;
+        writeln!(f, "#Split orders (batches) {}", self.num_split_orders)?;
+        writeln!(f, "#Split orders (waives) {}", self.num_waive_split_orders)?;
+        match self.tour_model {
+            TourModel::Counting => writeln!(f, "Tour cost {:?}", self.tour_cost)?,
+            TourModel::Distance => writeln!(f, "Tour cost (distance) {:?}", self.tour_cost)?,
+        }
+        writeln!(f, "Rest cost (batches) {:?}", self.rest_cost_batches)?;
+        writeln!(f, "Rest cost (waives) {:?}", self.rest_cost_waives)?;
+        writeln!(f, "Split cost {:?}", self.split_cost)?;
+        writeln!(f)?;
+        writeln!(f, "Overall cost {}", self.overall_cost())?;
+        writeln!(f, "Lower bound {}", self.lower_bound)?;
+        write!(f, "Gap {:.2}%", self.gap() * 100.0)
+    }
+}
@@ -0,0 +1,304 @@
+//! Checks and scores an existing solution
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::bounds::LowerBound;
+use crate::ga::batches::Waive;
+use crate::ga::orders::{split_orders, Batch};
+use crate::model::*;
+use crate::output::Output;
+use crate::solver::CostBreakdown;
+
+/// A single reason why a solution is not valid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// An ordered article is not picked in any batch
+    MissingArticle { order_id: ID, article_id: ID },
+    /// An article is picked although it is not (or not that often) ordered
+    UnorderedArticle {
+        batch_id: ID,
+        order_id: ID,
+        article_id: ID,
+    },
+    DuplicateBatchId { batch_id: ID },
+    DuplicateWaveId { wave_id: ID },
+    BatchExceedsMaxWeight {
+        batch_id: ID,
+        volume: Volume,
+        max_weight: Volume,
+    },
+    WaveExceedsMaxArticles {
+        wave_id: ID,
+        num_articles: usize,
+        max_articles: usize,
+    },
+    WrongBatchVolume {
+        batch_id: ID,
+        stated: Volume,
+        actual: Volume,
+    },
+    WrongWaveSize {
+        wave_id: ID,
+        stated: usize,
+        actual: usize,
+    },
+    WrongOrderIds {
+        wave_id: ID,
+        stated: BTreeSet<ID>,
+        actual: BTreeSet<ID>,
+    },
+    UnknownBatch { wave_id: ID, batch_id: ID },
+    BatchWithoutWave { batch_id: ID },
+    BatchInManyWaves { batch_id: ID },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::MissingArticle {
+                order_id,
+                article_id,
+            } => write!(
+                f,
+                "Article {} of order {} is not picked",
+                article_id, order_id
+            ),
+            Violation::UnorderedArticle {
+                batch_id,
+                order_id,
+                article_id,
+            } => write!(
+                f,
+                "Batch {} picks article {} for order {}, which is not ordered (that often)",
+                batch_id, article_id, order_id
+            ),
+            Violation::DuplicateBatchId { batch_id } => {
+                write!(f, "Batch id {} is used more than once", batch_id)
+            }
+            Violation::DuplicateWaveId { wave_id } => {
+                write!(f, "Wave id {} is used more than once", wave_id)
+            }
+            Violation::BatchExceedsMaxWeight {
+                batch_id,
+                volume,
+                max_weight,
+            } => write!(
+                f,
+                "Batch {} has volume {} which exceeds the max weight of {}",
+                batch_id, volume, max_weight
+            ),
+            Violation::WaveExceedsMaxArticles {
+                wave_id,
+                num_articles,
+                max_articles,
+            } => write!(
+                f,
+                "Wave {} has {} articles which exceeds the max of {}",
+                wave_id, num_articles, max_articles
+            ),
+            Violation::WrongBatchVolume {
+                batch_id,
+                stated,
+                actual,
+            } => write!(
+                f,
+                "Batch {} states volume {} but has volume {}",
+                batch_id, stated, actual
+            ),
+            Violation::WrongWaveSize {
+                wave_id,
+                stated,
+                actual,
+            } => write!(
+                f,
+                "Wave {} states size {} but has {} articles",
+                wave_id, stated, actual
+            ),
+            Violation::WrongOrderIds {
+                wave_id,
+                stated,
+                actual,
+            } => write!(
+                f,
+                "Wave {} states order ids {:?} but contains orders {:?}",
+                wave_id, stated, actual
+            ),
+            Violation::UnknownBatch { wave_id, batch_id } => {
+                write!(f, "Wave {} contains unknown batch {}", wave_id, batch_id)
+            }
+            Violation::BatchWithoutWave { batch_id } => {
+                write!(f, "Batch {} is not part of any wave", batch_id)
+            }
+            Violation::BatchInManyWaves { batch_id } => {
+                write!(f, "Batch {} is part of more than one wave", batch_id)
+            }
+        }
+    }
+}
+
+/// Result of [`validate_output`]
+#[derive(Debug)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+    /// Cost of the solution, as far as it could be attributed to ordered articles
+    pub cost: CostBreakdown,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Checks that `output` is a valid solution for `model` and calculates its cost
+pub fn validate_output(model: &Model, output: &Output) -> ValidationReport {
+    let cost_model = model.cost_model();
+    let mut violations = Vec::new();
+
+    // ordered articles, that are not picked (yet), by order and article id
+    let mut unpicked_articles: BTreeMap<(ID, ID), Vec<&OrderedArticle>> = BTreeMap::new();
+    model.get_ordered_articles().into_iter().for_each(|article| {
+        unpicked_articles
+            .entry((article.order_id, article.id))
+            .or_default()
+            .push(article)
+    });
+
+    let mut batches: BTreeMap<ID, Batch> = BTreeMap::new();
+    for output_batch in output.batches.iter() {
+        let batch_id = output_batch.batch_id;
+        let mut batch = Batch::new(batch_id as usize);
+
+        for item in output_batch.items.iter() {
+            let article = unpicked_articles
+                .get_mut(&(item.order_id, item.article_id))
+                .and_then(Vec::pop);
+
+            match article {
+                Some(article) => batch.push(article),
+                None => violations.push(Violation::UnorderedArticle {
+                    batch_id,
+                    order_id: item.order_id,
+                    article_id: item.article_id,
+                }),
+            }
+        }
+
+        if batch.volume() > cost_model.max_weight_per_batch {
+            violations.push(Violation::BatchExceedsMaxWeight {
+                batch_id,
+                volume: batch.volume(),
+                max_weight: cost_model.max_weight_per_batch,
+            });
+        }
+        if batch.volume() != output_batch.batch_volume {
+            violations.push(Violation::WrongBatchVolume {
+                batch_id,
+                stated: output_batch.batch_volume,
+                actual: batch.volume(),
+            });
+        }
+
+        if batches.insert(batch_id, batch).is_some() {
+            violations.push(Violation::DuplicateBatchId { batch_id });
+        }
+    }
+
+    unpicked_articles
+        .values()
+        .flatten()
+        .for_each(|article| {
+            violations.push(Violation::MissingArticle {
+                order_id: article.order_id,
+                article_id: article.id,
+            })
+        });
+
+    let mut wave_ids = BTreeSet::new();
+    let mut waves_per_batch: BTreeMap<ID, usize> = BTreeMap::new();
+    let mut waives = Vec::new();
+    for output_wave in output.waves.iter() {
+        let wave_id = output_wave.wave_id;
+        if !wave_ids.insert(wave_id) {
+            violations.push(Violation::DuplicateWaveId { wave_id });
+        }
+
+        let mut waive = Waive::new();
+        for batch_id in output_wave.batch_ids.iter() {
+            match batches.get(batch_id) {
+                Some(batch) => {
+                    waive.push(batch.clone());
+                    *waves_per_batch.entry(*batch_id).or_default() += 1;
+                }
+                None => violations.push(Violation::UnknownBatch {
+                    wave_id,
+                    batch_id: *batch_id,
+                }),
+            }
+        }
+
+        if waive.num_articles() > cost_model.max_articles_per_waive {
+            violations.push(Violation::WaveExceedsMaxArticles {
+                wave_id,
+                num_articles: waive.num_articles(),
+                max_articles: cost_model.max_articles_per_waive,
+            });
+        }
+        if waive.num_articles() != output_wave.wave_size {
+            violations.push(Violation::WrongWaveSize {
+                wave_id,
+                stated: output_wave.wave_size,
+                actual: waive.num_articles(),
+            });
+        }
+        let stated_order_ids = output_wave.order_ids.iter().copied().collect();
+        if waive.order_ids_in_waive() != stated_order_ids {
+            violations.push(Violation::WrongOrderIds {
+                wave_id,
+                stated: stated_order_ids,
+                actual: waive.order_ids_in_waive(),
+            });
+        }
+
+        waives.push(waive);
+    }
+
+    for batch_id in batches.keys() {
+        match waves_per_batch.get(batch_id) {
+            None => violations.push(Violation::BatchWithoutWave {
+                batch_id: *batch_id,
+            }),
+            Some(n) if *n > 1 => violations.push(Violation::BatchInManyWaves {
+                batch_id: *batch_id,
+            }),
+            _ => {}
+        }
+    }
+
+    let mut waves_per_order: BTreeMap<ID, usize> = BTreeMap::new();
+    waives
+        .iter()
+        .flat_map(Waive::order_ids_in_waive)
+        .for_each(|order_id| *waves_per_order.entry(order_id).or_default() += 1);
+    let num_split_orders = split_orders(batches.values()).len();
+    let num_waive_split_orders = waves_per_order.values().filter(|n| **n > 1).count();
+
+    let cost = CostBreakdown {
+        tour_model: cost_model.tour_model,
+        num_waives: waives.len(),
+        num_batches: batches.len(),
+        num_split_orders,
+        num_waive_split_orders,
+        tour_cost: batches
+            .values()
+            .map(|batch| batch.tour_cost(cost_model))
+            .sum(),
+        rest_cost_batches: batches.len() * cost_model.cost_per_batch,
+        rest_cost_waives: waives.len() * cost_model.cost_per_waive,
+        split_cost: num_split_orders * cost_model.penalty_per_batch_split
+            + num_waive_split_orders * cost_model.penalty_per_waive_split,
+        lower_bound: LowerBound::new(model).overall_cost(),
+    };
+
+    ValidationReport { violations, cost }
+}
@@ -0,0 +1,222 @@
+//! Waivings whose cost is kept up to date while batches move between waives
+use std::collections::BTreeMap;
+
+use crate::cost::CostModel;
+use crate::ga::batches::{WaiveId, WaiveMapping};
+use crate::ga::orders::BatchedArticles;
+use crate::model::*;
+
+/// A mapping from batches (by index) to waives, with counters per waive and per order
+///
+/// Moving a batch only updates the counters of the two waives and of the orders of the batch.
+#[derive(Debug, Clone)]
+pub struct Waiving<'a> {
+    cost_model: &'a CostModel,
+    waive_mapping: WaiveMapping,
+    /// Number of articles of every batch, by index
+    batch_articles: Vec<usize>,
+    /// Orders of every batch, by index
+    batch_orders: Vec<Vec<ID>>,
+    /// Number of articles per waive id
+    waive_articles: Vec<usize>,
+    /// Number of batches per waive id
+    waive_batches: Vec<usize>,
+    num_waives: usize,
+    /// Sum of the squared number of articles of every waive
+    concentration: usize,
+    /// Number of batches of every order per waive, by order id
+    orders: BTreeMap<ID, BTreeMap<WaiveId, usize>>,
+    num_split_orders: usize,
+}
+
+impl<'a> Waiving<'a> {
+    pub fn new(
+        cost_model: &'a CostModel,
+        batched_articles: &BatchedArticles,
+        waive_mapping: &WaiveMapping,
+    ) -> Waiving<'a> {
+        let batches = batched_articles.to_batches();
+        let num_waive_ids = waive_mapping
+            .iter()
+            .max()
+            .map_or(0, |waive_id| waive_id + 1)
+            .max(batches.len());
+
+        let mut waiving = Waiving {
+            cost_model,
+            waive_mapping: waive_mapping.clone(),
+            batch_articles: batches.iter().map(|batch| batch.num_articles()).collect(),
+            batch_orders: batches
+                .iter()
+                .map(|batch| batch.order_ids_in_batch().into_iter().collect())
+                .collect(),
+            waive_articles: vec![0; num_waive_ids],
+            waive_batches: vec![0; num_waive_ids],
+            num_waives: 0,
+            concentration: 0,
+            orders: BTreeMap::new(),
+            num_split_orders: 0,
+        };
+        for (idx, waive_id) in waive_mapping.iter().enumerate() {
+            waiving.add(idx, *waive_id);
+        }
+        waiving
+    }
+
+    pub fn waive_mapping(&self) -> &WaiveMapping {
+        &self.waive_mapping
+    }
+
+    pub fn into_waive_mapping(self) -> WaiveMapping {
+        self.waive_mapping
+    }
+
+    /// Rest cost of the waives plus the penalties for orders split across waives, both the soft
+    /// one and the one that is part of the cost, like the genetic search
+    pub fn cost(&self) -> usize {
+        self.num_waives * self.cost_model.cost_per_waive
+            + self.num_split_orders
+                * (self.cost_model.penalty_per_split_order
+                    + self.cost_model.penalty_per_waive_split)
+    }
+
+    pub fn cost_model(&self) -> &'a CostModel {
+        self.cost_model
+    }
+
+    pub fn num_batches(&self) -> usize {
+        self.waive_mapping.len()
+    }
+
+    pub fn num_waives(&self) -> usize {
+        self.num_waives
+    }
+
+    /// Number of orders whose batches are in more than one waive
+    pub fn num_split_orders(&self) -> usize {
+        self.num_split_orders
+    }
+
+    /// Sum of the squared number of articles of every waive
+    ///
+    /// Grows as the articles gather in fewer waives, even while the number of waives stays.
+    pub fn concentration(&self) -> usize {
+        self.concentration
+    }
+
+    pub fn waive_of(&self, idx: usize) -> WaiveId {
+        self.waive_mapping[idx]
+    }
+
+    /// Number of articles of a batch, by index
+    pub fn batch_articles(&self, idx: usize) -> usize {
+        self.batch_articles[idx]
+    }
+
+    /// Number of articles of a waive, 0 if it is empty
+    pub fn waive_articles(&self, waive_id: WaiveId) -> usize {
+        self.waive_articles[waive_id]
+    }
+
+    pub fn is_empty_waive(&self, waive_id: WaiveId) -> bool {
+        self.waive_batches[waive_id] == 0
+    }
+
+    /// Ids of the waives that hold at least one batch
+    pub fn non_empty_waive_ids(&self) -> Vec<WaiveId> {
+        (0..self.waive_batches.len())
+            .filter(|waive_id| self.waive_batches[*waive_id] > 0)
+            .collect()
+    }
+
+    /// First waive without batches
+    pub fn empty_waive_id(&self) -> Option<WaiveId> {
+        (0..self.waive_batches.len()).find(|waive_id| self.waive_batches[*waive_id] == 0)
+    }
+
+    /// Whether the batch fits into the waive without exceeding the max articles
+    ///
+    /// Always true for the waive that already holds the batch.
+    pub fn fits(&self, idx: usize, waive_id: WaiveId) -> bool {
+        self.waive_mapping[idx] == waive_id
+            || self.waive_articles[waive_id] + self.batch_articles[idx]
+                <= self.cost_model.max_articles_per_waive
+    }
+
+    /// Whether two batches of different waives fit after trading their waives
+    pub fn fits_swapped(&self, idx: usize, other_idx: usize) -> bool {
+        let (waive_id, other_waive_id) = (self.waive_mapping[idx], self.waive_mapping[other_idx]);
+        let (articles, other_articles) = (self.batch_articles[idx], self.batch_articles[other_idx]);
+        let max_articles = self.cost_model.max_articles_per_waive;
+
+        self.waive_articles[other_waive_id] + articles - other_articles <= max_articles
+            && self.waive_articles[waive_id] + other_articles - articles <= max_articles
+    }
+
+    /// Moves a batch into another waive and updates the counters of both waives
+    pub fn move_batch(&mut self, idx: usize, to: WaiveId) {
+        if self.waive_mapping[idx] != to {
+            self.remove(idx);
+            self.add(idx, to);
+        }
+    }
+
+    /// Trades the waives of two batches
+    pub fn swap_batches(&mut self, idx: usize, other_idx: usize) {
+        let (waive_id, other_waive_id) = (self.waive_mapping[idx], self.waive_mapping[other_idx]);
+        self.move_batch(idx, other_waive_id);
+        self.move_batch(other_idx, waive_id);
+    }
+
+    fn add(&mut self, idx: usize, waive_id: WaiveId) {
+        self.waive_mapping[idx] = waive_id;
+        self.set_waive_articles(
+            waive_id,
+            self.waive_articles[waive_id] + self.batch_articles[idx],
+        );
+        self.waive_batches[waive_id] += 1;
+        if self.waive_batches[waive_id] == 1 {
+            self.num_waives += 1;
+        }
+
+        for order_id in self.batch_orders[idx].iter() {
+            let waives = self.orders.entry(*order_id).or_default();
+            let was_split = waives.len() > 1;
+            *waives.entry(waive_id).or_default() += 1;
+            if !was_split && waives.len() > 1 {
+                self.num_split_orders += 1;
+            }
+        }
+    }
+
+    fn remove(&mut self, idx: usize) {
+        let waive_id = self.waive_mapping[idx];
+        self.set_waive_articles(
+            waive_id,
+            self.waive_articles[waive_id] - self.batch_articles[idx],
+        );
+        self.waive_batches[waive_id] -= 1;
+        if self.waive_batches[waive_id] == 0 {
+            self.num_waives -= 1;
+        }
+
+        for order_id in self.batch_orders[idx].iter() {
+            let waives = self.orders.get_mut(order_id).expect("order of batch");
+            let was_split = waives.len() > 1;
+            let count = waives.get_mut(&waive_id).expect("batch not in waive");
+            *count -= 1;
+            if *count == 0 {
+                waives.remove(&waive_id);
+            }
+            if was_split && waives.len() <= 1 {
+                self.num_split_orders -= 1;
+            }
+        }
+    }
+
+    fn set_waive_articles(&mut self, waive_id: WaiveId, num_articles: usize) {
+        let before = self.waive_articles[waive_id];
+        self.concentration = self.concentration + num_articles * num_articles - before * before;
+        self.waive_articles[waive_id] = num_articles;
+    }
+}
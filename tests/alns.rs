@@ -0,0 +1,129 @@
+use std::time::Instant;
+
+use order_batching::ga::orders::{article_groups, BatchedArticles};
+use order_batching::heuristics::alns::{alns_batches, AlnsParams};
+use order_batching::heuristics::greedy::{greedy_batches, greedy_encoded_batches};
+use order_batching::{
+    load_input, validate_output, Algorithm, BatchEncoding, CostModel, Model, Solver,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+#[test]
+fn alns_is_valid_and_not_worse_than_greedy() {
+    let model = model(CostModel::default());
+
+    let greedy = Solver::new(&model)
+        .algorithm(Algorithm::Greedy)
+        .local_search(false)
+        .solve()
+        .unwrap();
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Alns)
+        .local_search(false)
+        .seed(5)
+        .solve()
+        .unwrap();
+
+    let report = validate_output(&model, &solution.output);
+    assert_eq!(report.violations, vec![]);
+    assert_eq!(report.cost, solution.cost);
+    assert!(solution.cost.overall_cost() <= greedy.cost.overall_cost());
+
+    let stats = solution.alns_stats.unwrap();
+    assert!(stats.cost_after <= stats.cost_before);
+    assert_eq!(stats.destroy.len(), 3);
+    assert_eq!(stats.repair.len(), 2);
+    for operators in [&stats.destroy, &stats.repair] {
+        let uses = operators
+            .iter()
+            .map(|operator| operator.uses)
+            .sum::<usize>();
+        assert_eq!(uses, stats.iterations);
+    }
+}
+
+#[test]
+fn alns_keeps_genes_together() {
+    let model = model(CostModel {
+        max_weight_per_batch: 3000,
+        ..CostModel::default()
+    });
+    let batched_articles = greedy_encoded_batches(&model, BatchEncoding::Orders);
+    let params = AlnsParams {
+        iterations: 300,
+        seed: 7,
+        ..AlnsParams::default()
+    };
+
+    let (alns_batched, stats) =
+        alns_batches(&model, &batched_articles, BatchEncoding::Orders, &params);
+
+    assert_eq!(stats.iterations, 300);
+    let batch_mapping = alns_batched.batch_mapping();
+    for group in article_groups(&model, BatchEncoding::Orders) {
+        assert!(group
+            .iter()
+            .all(|idx| batch_mapping[*idx] == batch_mapping[group[0]]));
+    }
+    for batch in alns_batched.to_batches() {
+        assert!(batch.volume() <= 3000);
+    }
+    assert_eq!(
+        alns_batched.cost(model.cost_model()),
+        Some(stats.cost_after)
+    );
+}
+
+#[test]
+fn alns_stops_at_deadline() {
+    let model = model(CostModel::default());
+    let batched_articles = greedy_batches(&model);
+    let params = AlnsParams {
+        deadline: Some(Instant::now()),
+        ..AlnsParams::default()
+    };
+
+    let (alns_batched, stats) =
+        alns_batches(&model, &batched_articles, BatchEncoding::Articles, &params);
+
+    assert!(stats.timed_out);
+    assert_eq!(stats.iterations, 0);
+    assert_eq!(stats.cost_after, stats.cost_before);
+    assert_eq!(
+        alns_batched.batch_mapping(),
+        batched_articles.batch_mapping()
+    );
+}
+
+#[test]
+fn alns_improves_batches_without_an_empty_batch() {
+    let model = model(CostModel::default());
+    // every article has a batch of its own, so only the pool is left to hold the removed articles
+    let batch_mapping = (0..model.get_ordered_articles().len()).collect::<Vec<_>>();
+    let batched_articles = BatchedArticles::from_batch_mapping(batch_mapping.clone(), &model);
+    let params = AlnsParams {
+        iterations: 100,
+        seed: 3,
+        ..AlnsParams::default()
+    };
+
+    let (found, stats) = alns_batches(&model, &batched_articles, BatchEncoding::Articles, &params);
+
+    assert_eq!(stats.iterations, 100);
+    assert!(stats.cost_after < stats.cost_before);
+    let improvements = stats
+        .destroy
+        .iter()
+        .map(|operator| operator.improvements)
+        .sum::<usize>();
+    assert!(improvements > 0);
+    assert!(found
+        .batch_mapping()
+        .iter()
+        .all(|batch_id| *batch_id < batch_mapping.len()));
+    assert_eq!(found.cost(model.cost_model()), Some(stats.cost_after));
+}
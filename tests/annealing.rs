@@ -0,0 +1,141 @@
+use order_batching::ga::orders::BatchedArticles;
+use order_batching::heuristics::annealing::{
+    anneal_batches, anneal_waives, Cooling, SaMoves, SaParams,
+};
+use order_batching::heuristics::greedy::{greedy_batches, greedy_waives};
+use order_batching::input::{Article, ArticleLocation, Input, Order};
+use order_batching::{
+    load_input, validate_output, Algorithm, BatchEncoding, CostModel, Model, Solver,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+#[test]
+fn annealing_is_valid_and_not_worse_than_greedy() {
+    let model = model(CostModel::default());
+
+    let greedy = Solver::new(&model)
+        .algorithm(Algorithm::Greedy)
+        .local_search(false)
+        .solve()
+        .unwrap();
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Sa)
+        .local_search(false)
+        .seed(5)
+        .solve()
+        .unwrap();
+
+    let report = validate_output(&model, &solution.output);
+    assert_eq!(report.violations, vec![]);
+    assert_eq!(report.cost, solution.cost);
+    assert!(solution.cost.overall_cost() <= greedy.cost.overall_cost());
+
+    let batch_stats = solution.batch_annealing_stats.unwrap();
+    assert!(batch_stats.cost_after <= batch_stats.cost_before);
+    assert!(solution.waive_annealing_stats.is_some());
+    assert!(solution.batch_stats.is_none());
+}
+
+#[test]
+fn annealed_waives_stay_within_max_articles() {
+    let model = model(CostModel {
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+    let batched_articles = greedy_batches(&model);
+    let greedy = greedy_waives(&model, &batched_articles);
+    let waive_mapping = greedy.waive_mapping();
+
+    for moves in [SaMoves::Move, SaMoves::Swap, SaMoves::Both] {
+        let params = SaParams {
+            moves,
+            seed: 1,
+            ..SaParams::default()
+        };
+        let (waived_batches, stats) =
+            anneal_waives(&model, &batched_articles, waive_mapping, &params);
+
+        assert!(stats.cost_after <= stats.cost_before);
+        for waive in waived_batches.to_waives() {
+            assert!(waive.num_articles() <= 20);
+        }
+    }
+}
+
+#[test]
+fn both_cooling_schedules_keep_batches_valid() {
+    let model = model(CostModel::default());
+    let batched_articles = greedy_batches(&model);
+    let max_weight = model.cost_model().max_weight_per_batch;
+
+    for cooling in [Cooling::Geometric, Cooling::Linear] {
+        let params = SaParams {
+            cooling,
+            temperature_steps: 50,
+            seed: 2,
+            ..SaParams::default()
+        };
+        let (annealed, stats) =
+            anneal_batches(&model, &batched_articles, BatchEncoding::Articles, &params);
+
+        assert_eq!(stats.iterations, 50 * params.moves_per_temperature);
+        assert!(!stats.timed_out);
+        assert!(stats.cost_after <= stats.cost_before);
+        assert_eq!(
+            Some(stats.cost_after),
+            annealed
+                .cost(model.cost_model())
+                .map(|cost| cost + annealed.split_cost(model.cost_model()))
+        );
+        for batch in annealed.to_batches() {
+            assert!(batch.volume() <= max_weight);
+        }
+    }
+}
+
+#[test]
+fn full_batches_improve_through_an_empty_batch() {
+    // articles 0 and 2 share aisle 0, articles 1 and 3 aisle 1, and two of them fill a batch
+    let input = Input {
+        article_locations: (0..4)
+            .map(|id| ArticleLocation {
+                warehouse: 0,
+                aisle: id % 2,
+                position: 0,
+                article_id: id,
+            })
+            .collect(),
+        orders: vec![Order {
+            order_id: 0,
+            article_ids: vec![0, 1, 2, 3],
+        }],
+        articles: (0..4)
+            .map(|article_id| Article {
+                article_id,
+                volume: 500,
+            })
+            .collect(),
+    };
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+    // both batches visit both aisles, and no article fits into the other batch
+    let batched_articles = BatchedArticles::from_batch_mapping(vec![0, 0, 1, 1], &model);
+    let params = SaParams {
+        moves: SaMoves::Move,
+        start_temperature: 100.0,
+        seed: 3,
+        ..SaParams::default()
+    };
+
+    let (annealed, stats) =
+        anneal_batches(&model, &batched_articles, BatchEncoding::Articles, &params);
+
+    assert!(stats.cost_after < stats.cost_before);
+    let batch_mapping = annealed.batch_mapping();
+    assert_eq!(batch_mapping[0], batch_mapping[2]);
+    assert_eq!(batch_mapping[1], batch_mapping[3]);
+    assert_ne!(batch_mapping[0], batch_mapping[1]);
+}
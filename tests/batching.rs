@@ -0,0 +1,88 @@
+use order_batching::batching::Evaluator;
+use order_batching::ga::orders::BatchedArticles;
+use order_batching::{load_input, CostModel, Model, TourModel};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+/// Moves articles around pseudo-randomly and compares against a full rebuild after every move
+fn assert_matches_rebuild(model: &Model) {
+    let evaluator = Evaluator::new(model);
+    let num_articles = evaluator.num_articles();
+    let mut batching = evaluator.batching((0..num_articles).collect());
+
+    let mut state = 17usize;
+    for step in 0..300 {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let idx = (state >> 33) % num_articles;
+        // few target batches, so that batches grow until they exceed the max weight
+        let to = (state >> 13) % (num_articles / 10).max(1);
+        batching.move_article(idx, to);
+
+        let batch_mapping = batching.batch_mapping().clone();
+        let rebuilt = BatchedArticles::from_batch_mapping(batch_mapping.clone(), model);
+        let cost = rebuilt.cost(model.cost_model());
+        let split_cost = rebuilt.split_cost(model.cost_model());
+        assert_eq!(evaluator.cost(&batch_mapping), cost, "step {}", step);
+        assert_eq!(
+            evaluator.split_cost(&batch_mapping),
+            split_cost,
+            "step {}",
+            step
+        );
+        assert_eq!(
+            batching.num_split_orders(),
+            rebuilt.get_split_orders().len(),
+            "step {}",
+            step
+        );
+        match cost {
+            Some(cost) => {
+                assert!(batching.is_valid(), "step {}", step);
+                assert_eq!(batching.cost(), cost + split_cost, "step {}", step);
+            }
+            None => assert!(!batching.is_valid(), "step {}", step),
+        }
+    }
+}
+
+#[test]
+fn incremental_cost_matches_rebuild() {
+    assert_matches_rebuild(&model(CostModel::default()));
+}
+
+#[test]
+fn incremental_cost_matches_rebuild_with_distance() {
+    assert_matches_rebuild(&model(CostModel {
+        tour_model: TourModel::Distance,
+        ..CostModel::default()
+    }));
+}
+
+#[test]
+fn incremental_cost_matches_rebuild_with_split_penalty() {
+    assert_matches_rebuild(&model(CostModel {
+        penalty_per_batch_split: 4,
+        ..CostModel::default()
+    }));
+}
+
+#[test]
+fn merged_cost_matches_moving_all_articles() {
+    let model = model(CostModel::default());
+    let evaluator = Evaluator::new(&model);
+    let mut batching =
+        evaluator.batching((0..evaluator.num_articles()).map(|idx| idx % 4).collect());
+
+    let merged_cost = batching.merged_cost(1, 2);
+    for idx in batching.articles_of(1) {
+        batching.move_article(idx, 2);
+    }
+
+    assert_eq!(batching.batch_cost(1), 0);
+    assert_eq!(batching.batch_cost(2), merged_cost);
+}
@@ -0,0 +1,96 @@
+use order_batching::bounds::{bin_packing_bound, LowerBound};
+use order_batching::exact::ExactParams;
+use order_batching::input::{Article, ArticleLocation, Order};
+use order_batching::{load_input, Algorithm, CostModel, Input, Model, Solver, TourModel};
+
+#[test]
+fn bin_packing_bound_counts_large_items() {
+    assert_eq!(bin_packing_bound(&[], 1000), 0);
+    // the volume fits into two batches, but no two of these items share one
+    assert_eq!(bin_packing_bound(&[600, 600, 600], 1000), 3);
+    assert_eq!(bin_packing_bound(&[600, 400, 400, 600], 1000), 2);
+    // 500 fits into the room of neither 600
+    assert_eq!(bin_packing_bound(&[600, 600, 500, 300], 1000), 3);
+}
+
+#[test]
+fn lower_bound_is_below_found_solutions() {
+    let input = load_input("input1.txt").unwrap();
+
+    for tour_model in [TourModel::Counting, TourModel::Distance] {
+        let model = Model::from_input(
+            &input,
+            CostModel {
+                tour_model,
+                max_articles_per_waive: 20,
+                ..CostModel::default()
+            },
+        )
+        .unwrap();
+        let lower_bound = LowerBound::new(&model);
+        assert!(lower_bound.num_batches >= model.min_batches_num());
+        assert_eq!(lower_bound.num_waives, 3);
+
+        for algorithm in [Algorithm::Greedy, Algorithm::Ga] {
+            let solution = Solver::new(&model)
+                .algorithm(algorithm)
+                .batch_population(20)
+                .batch_generations(10)
+                .waive_population(20)
+                .waive_generations(10)
+                .seed(1)
+                .solve()
+                .unwrap();
+
+            let cost = solution.cost;
+            assert_eq!(cost.lower_bound, lower_bound.overall_cost());
+            assert!(cost.lower_bound <= cost.overall_cost());
+            assert!(cost.num_batches >= lower_bound.num_batches);
+            assert!(cost.num_waives >= lower_bound.num_waives);
+            assert!(cost.gap() >= 0.0 && cost.gap() < 1.0);
+            assert_eq!(solution.output.lower_bound, Some(cost.lower_bound));
+            assert_eq!(solution.output.gap, Some(cost.gap()));
+        }
+    }
+}
+
+#[test]
+fn optimal_exact_solution_closes_the_gap() {
+    let article_ids = (0..4).collect::<Vec<u16>>();
+    let input = Input {
+        article_locations: article_ids
+            .iter()
+            .map(|id| ArticleLocation {
+                warehouse: 0,
+                aisle: *id / 2,
+                position: *id,
+                article_id: *id,
+            })
+            .collect(),
+        orders: vec![Order {
+            order_id: 0,
+            article_ids: article_ids.clone(),
+        }],
+        articles: article_ids
+            .iter()
+            .map(|id| Article {
+                article_id: *id,
+                volume: 300,
+            })
+            .collect(),
+    };
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Exact)
+        .exact(ExactParams {
+            node_limit: None,
+            deadline: None,
+        })
+        .solve()
+        .unwrap();
+
+    assert_eq!(solution.cost.lower_bound, solution.cost.overall_cost());
+    assert_eq!(solution.cost.gap(), 0.0);
+    assert!(format!("{}", solution.cost).ends_with("Gap 0.00%"));
+}
@@ -0,0 +1,85 @@
+use order_batching::{load_cost_model, load_input, CostModel, InputError, Model};
+
+fn write_temp(name: &str, content: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn loads_partial_json_cost_model() {
+    let path = write_temp(
+        "order-batching-cost-model.json",
+        r#"{ "max_weight_per_batch": 600, "cost_per_aisle": 7 }"#,
+    );
+
+    let cost_model = load_cost_model(&path).unwrap();
+
+    assert_eq!(
+        cost_model,
+        CostModel {
+            max_weight_per_batch: 600,
+            cost_per_aisle: 7,
+            ..CostModel::default()
+        }
+    );
+}
+
+#[test]
+fn loads_toml_cost_model() {
+    let path = write_temp(
+        "order-batching-cost-model.toml",
+        "max_articles_per_waive = 100\ncost_per_waive = 20\n",
+    );
+
+    let cost_model = load_cost_model(&path).unwrap();
+
+    assert_eq!(cost_model.max_articles_per_waive, 100);
+    assert_eq!(cost_model.cost_per_waive, 20);
+    assert_eq!(cost_model.cost_per_batch, CostModel::default().cost_per_batch);
+}
+
+#[test]
+fn rejects_unknown_keys() {
+    let path = write_temp(
+        "order-batching-cost-model-unknown.json",
+        r#"{ "cost_per_shelf": 1 }"#,
+    );
+
+    assert!(load_cost_model(&path).is_err());
+}
+
+#[test]
+fn rejects_zero_capacities() {
+    let path = write_temp(
+        "order-batching-cost-model-zero.toml",
+        "max_articles_per_waive = 0\n",
+    );
+    assert!(load_cost_model(&path).is_err());
+
+    let cost_model = CostModel {
+        max_weight_per_batch: 0,
+        ..CostModel::default()
+    };
+    assert_eq!(
+        cost_model.validate(),
+        Err(InputError::ZeroCapacity {
+            capacity: "max_weight_per_batch"
+        })
+    );
+    assert!(CostModel::default().validate().is_ok());
+
+    // library callers that skip the validation get an error instead of a panic
+    let input = load_input("input1.txt").unwrap();
+    let cost_model = CostModel {
+        max_articles_per_waive: 0,
+        ..CostModel::default()
+    };
+    let errors = Model::from_input(&input, cost_model).unwrap_err().errors;
+    assert_eq!(
+        errors,
+        vec![InputError::ZeroCapacity {
+            capacity: "max_articles_per_waive"
+        }]
+    );
+}
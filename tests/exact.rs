@@ -0,0 +1,323 @@
+use order_batching::bounds::LowerBound;
+use order_batching::exact::{formulate, solve_exact, ExactError, ExactParams, ExactStatus};
+use order_batching::heuristics::greedy::{greedy_batches, greedy_waives};
+use order_batching::input::{Article, ArticleLocation, Order};
+use order_batching::{
+    load_input, validate_output, Algorithm, CostModel, Input, Model, Solver, TourModel,
+};
+
+/// Builds an input with two orders over two aisles, which needs at least two batches
+fn small_input() -> Input {
+    let volumes = [600, 500, 400, 300, 200];
+
+    Input {
+        article_locations: (0..volumes.len() as u16)
+            .map(|id| ArticleLocation {
+                warehouse: 0,
+                aisle: id % 2,
+                position: id,
+                article_id: id,
+            })
+            .collect(),
+        orders: vec![
+            Order {
+                order_id: 0,
+                article_ids: vec![0, 1, 2],
+            },
+            Order {
+                order_id: 1,
+                article_ids: vec![3, 4],
+            },
+        ],
+        articles: volumes
+            .iter()
+            .enumerate()
+            .map(|(id, volume)| Article {
+                article_id: id as u16,
+                volume: *volume,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn exact_search_solves_a_small_input_to_optimality() {
+    let model = Model::from_input(&small_input(), CostModel::default()).unwrap();
+    let batched_articles = greedy_batches(&model);
+    let greedy_cost = batched_articles.cost(model.cost_model()).unwrap()
+        + greedy_waives(&model, &batched_articles).rest_cost(model.cost_model());
+
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Exact)
+        .exact(ExactParams {
+            node_limit: None,
+            deadline: None,
+        })
+        .solve()
+        .unwrap();
+
+    let report = validate_output(&model, &solution.output);
+    assert_eq!(report.violations, vec![]);
+    let stats = solution.exact_stats.unwrap();
+    assert_eq!(stats.status, ExactStatus::Optimal);
+    assert_eq!(stats.cost, solution.cost.overall_cost());
+    assert_eq!(stats.bound, stats.cost);
+    assert!(stats.cost <= greedy_cost);
+}
+
+#[test]
+fn node_limit_keeps_a_valid_solution_and_bound() {
+    let input = load_input("input1.txt").unwrap();
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+    let batched_articles = greedy_batches(&model);
+    let waive_mapping = greedy_waives(&model, &batched_articles)
+        .waive_mapping()
+        .clone();
+    let params = ExactParams {
+        node_limit: Some(3),
+        deadline: None,
+    };
+
+    let (found, found_waive_mapping, stats) =
+        solve_exact(&model, &batched_articles, &waive_mapping, params).unwrap();
+
+    assert_eq!(stats.status, ExactStatus::NodeLimit);
+    assert_eq!(stats.nodes, 3);
+    assert!(stats.bound <= stats.cost);
+    assert_eq!(found_waive_mapping.len(), found.to_batches().len());
+    assert!(found
+        .to_batches()
+        .iter()
+        .all(|batch| batch.volume() <= model.cost_model().max_weight_per_batch));
+}
+
+#[test]
+fn model_exports_as_lp_and_mps() {
+    let model = Model::from_input(&small_input(), CostModel::default()).unwrap();
+    let batched_articles = greedy_batches(&model);
+    let waived_batches = greedy_waives(&model, &batched_articles);
+    let formulation =
+        formulate(&model, &batched_articles, waived_batches.waive_mapping()).unwrap();
+
+    let mut lp = Vec::new();
+    formulation.write_lp(&mut lp).unwrap();
+    let lp = parse_lp(&String::from_utf8(lp).unwrap());
+    assert_eq!(lp.constraints.len(), formulation.num_constraints());
+    assert_eq!(lp.variables.len(), formulation.num_variables());
+    assert!(lp
+        .constraints
+        .contains(&"assign_a0: + 1 x_a0_b0 = 1".to_owned()));
+
+    let mut mps = Vec::new();
+    formulation.write_mps(&mut mps).unwrap();
+    let mps = parse_mps(&String::from_utf8(mps).unwrap());
+    assert_eq!(mps.rows.len(), formulation.num_constraints());
+    assert_eq!(mps.columns.len(), formulation.num_variables());
+    assert_eq!(mps.columns, lp.variables);
+    assert_eq!(mps.integers, lp.binaries);
+
+    let distance = Model::from_input(
+        &small_input(),
+        CostModel {
+            tour_model: TourModel::Distance,
+            ..CostModel::default()
+        },
+    )
+    .unwrap();
+    assert!(matches!(
+        formulate(&distance, &batched_articles, waived_batches.waive_mapping()),
+        Err(ExactError::Unsupported(_))
+    ));
+}
+
+#[test]
+fn search_starts_from_the_lower_bound() {
+    let input = load_input("input1.txt").unwrap();
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+    let batched_articles = greedy_batches(&model);
+    let waive_mapping = greedy_waives(&model, &batched_articles)
+        .waive_mapping()
+        .clone();
+    let params = ExactParams {
+        node_limit: Some(1),
+        deadline: None,
+    };
+
+    let (_, _, stats) = solve_exact(&model, &batched_articles, &waive_mapping, params).unwrap();
+
+    assert!(stats.bound >= LowerBound::new(&model).overall_cost());
+}
+
+#[test]
+fn exact_search_closes_the_gap_of_a_tiny_input() {
+    let mut input = small_input();
+    // a third order that spans both warehouses
+    input.article_locations.push(ArticleLocation {
+        warehouse: 1,
+        aisle: 0,
+        position: 0,
+        article_id: 5,
+    });
+    input.articles.push(Article {
+        article_id: 5,
+        volume: 700,
+    });
+    input.orders.push(Order {
+        order_id: 2,
+        article_ids: vec![5, 0],
+    });
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+    let batched_articles = greedy_batches(&model);
+    let waive_mapping = greedy_waives(&model, &batched_articles)
+        .waive_mapping()
+        .clone();
+    let params = ExactParams {
+        node_limit: None,
+        deadline: None,
+    };
+
+    let (found, found_waive_mapping, stats) =
+        solve_exact(&model, &batched_articles, &waive_mapping, params).unwrap();
+
+    assert_eq!(stats.status, ExactStatus::Optimal);
+    assert_eq!(stats.gap(), 0.0);
+    assert_eq!(stats.bound, stats.cost);
+    assert_eq!(found_waive_mapping.len(), found.to_batches().len());
+}
+
+/// Names of the sections, variables and constraints of an LP file
+struct LpFile {
+    /// Constraints with their terms on a single line
+    constraints: Vec<String>,
+    /// Continuous and binary variables, sorted by name
+    variables: Vec<String>,
+    /// Binary variables, sorted by name
+    binaries: Vec<String>,
+}
+
+fn parse_lp(lp: &str) -> LpFile {
+    let mut sections = Vec::new();
+    let mut file = LpFile {
+        constraints: Vec::new(),
+        variables: Vec::new(),
+        binaries: Vec::new(),
+    };
+
+    for line in lp.lines().filter(|line| !line.starts_with('\\')) {
+        if !line.starts_with(' ') {
+            sections.push(line);
+            continue;
+        }
+        let section = *sections.last().expect("line before the first section");
+        let continued = line.starts_with("   ");
+        match section {
+            "Minimize" => assert!(continued || line.starts_with(" obj:"), "{}", line),
+            "Subject To" if continued => {
+                let constraint = file.constraints.last_mut().expect("constraint");
+                constraint.push(' ');
+                constraint.push_str(line.trim());
+            }
+            "Subject To" => file.constraints.push(line.trim().to_owned()),
+            "Bounds" => match line.split_whitespace().collect::<Vec<_>>().as_slice() {
+                ["0", "<=", name, "<=", upper] => {
+                    upper.parse::<f64>().unwrap();
+                    file.variables.push(name.to_string());
+                }
+                fields => panic!("invalid bound {:?}", fields),
+            },
+            "Binaries" => file.binaries.push(line.trim().to_owned()),
+            _ => panic!("line in section {}: {}", section, line),
+        }
+    }
+
+    assert_eq!(
+        sections,
+        ["Minimize", "Subject To", "Bounds", "Binaries", "End"]
+    );
+    for constraint in file.constraints.iter() {
+        let fields = constraint.split_whitespace().collect::<Vec<_>>();
+        assert!(fields[0].ends_with(':'), "{}", constraint);
+        assert!(["<=", "=", ">="].contains(&fields[fields.len() - 2]));
+        fields[fields.len() - 1].parse::<f64>().unwrap();
+        // sign, coefficient and variable per term
+        assert_eq!((fields.len() - 3) % 3, 0, "{}", constraint);
+    }
+    file.variables.extend(file.binaries.iter().cloned());
+    file.variables.sort();
+    file.binaries.sort();
+    file
+}
+
+/// Names of the rows and columns of an MPS file
+struct MpsFile {
+    rows: Vec<String>,
+    /// Columns, sorted by name
+    columns: Vec<String>,
+    /// Columns between the integer markers, sorted by name
+    integers: Vec<String>,
+}
+
+fn parse_mps(mps: &str) -> MpsFile {
+    let mut sections = Vec::new();
+    let mut rows = Vec::new();
+    let mut columns: Vec<String> = Vec::new();
+    let mut integers = Vec::new();
+    let mut markers = Vec::new();
+    let mut bounds = Vec::new();
+
+    for line in mps.lines() {
+        if !line.starts_with(' ') {
+            sections.push(line.split_whitespace().next().unwrap());
+            continue;
+        }
+        let fields = line.split_whitespace().collect::<Vec<_>>();
+        match (*sections.last().expect("section"), fields.as_slice()) {
+            ("ROWS", ["N", "obj"]) => assert!(rows.is_empty(), "objective after the rows"),
+            ("ROWS", [sense, row]) if ["L", "E", "G"].contains(sense) => rows.push(row.to_string()),
+            ("COLUMNS", ["MARKER", "'MARKER'", marker]) => markers.push(marker.to_string()),
+            ("COLUMNS", [column, row, value]) => {
+                assert!(*row == "obj" || rows.iter().any(|r| r == row), "{}", line);
+                value.parse::<f64>().unwrap();
+                if columns.last().map(String::as_str) != Some(*column) {
+                    assert!(!columns.iter().any(|c| c == column), "{} split", column);
+                    columns.push(column.to_string());
+                    if markers.len() == 1 {
+                        integers.push(column.to_string());
+                    }
+                }
+            }
+            ("RHS", ["rhs", row, value]) => {
+                assert!(rows.iter().any(|r| r == row), "{}", line);
+                value.parse::<f64>().unwrap();
+            }
+            ("BOUNDS", ["UP", "bnd", column, value]) => {
+                value.parse::<f64>().unwrap();
+                bounds.push(column.to_string());
+            }
+            ("BOUNDS", ["BV", "bnd", column]) => {
+                assert!(
+                    integers.iter().any(|c| c == column),
+                    "{} not integer",
+                    column
+                );
+                bounds.push(column.to_string());
+            }
+            (section, _) => panic!("invalid line in section {}: {}", section, line),
+        }
+    }
+
+    assert_eq!(
+        sections,
+        ["NAME", "ROWS", "COLUMNS", "RHS", "BOUNDS", "ENDATA"]
+    );
+    assert_eq!(markers, ["'INTORG'", "'INTEND'"]);
+    columns.sort();
+    integers.sort();
+    bounds.sort();
+    assert_eq!(bounds, columns);
+    MpsFile {
+        rows,
+        columns,
+        integers,
+    }
+}
@@ -0,0 +1,61 @@
+use order_batching::heuristics::greedy::{greedy_batches, greedy_waives};
+use order_batching::{load_input, validate_output, Algorithm, CostModel, Model, Solver};
+
+fn model() -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, CostModel::default()).unwrap()
+}
+
+#[test]
+fn greedy_solution_is_valid() {
+    let model = model();
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Greedy)
+        .local_search(false)
+        .solve()
+        .unwrap();
+
+    assert!(solution.batch_stats.is_none());
+    assert!(solution.waive_stats.is_none());
+    assert_eq!(validate_output(&model, &solution.output).violations, vec![]);
+}
+
+#[test]
+fn greedy_batches_respect_limits() {
+    let model = model();
+    let cost_model = model.cost_model();
+    let batched = greedy_batches(&model);
+    let waived = greedy_waives(&model, &batched);
+
+    assert!(batched.to_batches().len() >= model.min_batches_num());
+    for batch in batched.to_batches() {
+        assert!(batch.volume() <= cost_model.max_weight_per_batch);
+    }
+    for waive in waived.to_waives() {
+        assert!(waive.num_articles() <= cost_model.max_articles_per_waive);
+    }
+}
+
+#[test]
+fn ga_is_not_worse_than_its_greedy_seed() {
+    let model = model();
+    let greedy = Solver::new(&model)
+        .algorithm(Algorithm::Greedy)
+        .local_search(false)
+        .solve()
+        .unwrap();
+    let ga = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .local_search(false)
+        .seed(5)
+        .solve()
+        .unwrap();
+
+    assert!(
+        ga.cost.tour_cost + ga.cost.rest_cost_batches
+            <= greedy.cost.tour_cost + greedy.cost.rest_cost_batches
+    );
+}
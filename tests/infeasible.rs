@@ -0,0 +1,84 @@
+use order_batching::ga::batches::WaivedBatches;
+use order_batching::ga::orders::BatchedArticles;
+use order_batching::heuristics::greedy::greedy_batches;
+use order_batching::heuristics::repair::{repair_oversized_waives, repair_overweight_batches};
+use order_batching::{
+    load_input, validate_output, Algorithm, CostModel, Infeasible, Model, Solver,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+#[test]
+fn repair_moves_articles_out_of_overweight_batches() {
+    let model = model(CostModel::default());
+    // every article in the first batch
+    let batch_mapping = vec![0; model.max_batches_num()];
+    assert!(
+        BatchedArticles::from_batch_mapping(batch_mapping.clone(), &model)
+            .cost(model.cost_model())
+            .is_none()
+    );
+
+    let repaired = repair_overweight_batches(&model, &batch_mapping);
+    let batched_articles = BatchedArticles::from_batch_mapping(repaired, &model);
+    assert!(batched_articles.cost(model.cost_model()).is_some());
+    let num_articles = batched_articles
+        .to_batches()
+        .iter()
+        .map(|batch| batch.num_articles())
+        .sum::<usize>();
+    assert_eq!(num_articles, model.max_batches_num());
+}
+
+#[test]
+fn repair_moves_batches_out_of_oversized_waives() {
+    let model = model(CostModel {
+        max_articles_per_waive: 12,
+        ..CostModel::default()
+    });
+    let batched_articles = greedy_batches(&model);
+    // every batch in the first waive
+    let waive_mapping = vec![0; batched_articles.to_batches().len()];
+
+    let repaired = repair_oversized_waives(&model, &batched_articles, &waive_mapping);
+    let waived_batches = WaivedBatches::from_waive_mapping(repaired, &batched_articles);
+    assert!(waived_batches.to_waives().len() > 1);
+    for waive in waived_batches.to_waives() {
+        assert!(waive.num_articles() <= 12);
+    }
+}
+
+#[test]
+fn every_way_to_handle_infeasible_individuals_finds_valid_solutions() {
+    let model = model(CostModel {
+        max_articles_per_waive: 12,
+        ..CostModel::default()
+    });
+
+    for infeasible in [Infeasible::Reject, Infeasible::Repair, Infeasible::Penalize] {
+        for algorithm in [Algorithm::Ga, Algorithm::Joint] {
+            let solution = Solver::new(&model)
+                .algorithm(algorithm)
+                .infeasible(infeasible)
+                .batch_population(20)
+                .batch_generations(10)
+                .waive_population(20)
+                .waive_generations(10)
+                .seed(4)
+                .solve()
+                .unwrap();
+
+            let report = validate_output(&model, &solution.output);
+            assert_eq!(
+                report.violations,
+                vec![],
+                "{:?} {:?}",
+                infeasible,
+                algorithm
+            );
+        }
+    }
+}
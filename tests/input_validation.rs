@@ -0,0 +1,132 @@
+use order_batching::input::{Article, ArticleLocation, Order};
+use order_batching::{load_input, CostModel, Input, InputError, Model};
+
+fn location(article_id: u16, aisle: u16) -> ArticleLocation {
+    ArticleLocation {
+        warehouse: 0,
+        aisle,
+        position: 0,
+        article_id,
+    }
+}
+
+fn article(article_id: u16, volume: u16) -> Article {
+    Article { article_id, volume }
+}
+
+#[test]
+fn input1_is_valid() {
+    let input = load_input("input1.txt").unwrap();
+
+    assert_eq!(input.validate(&CostModel::default()), Ok(()));
+}
+
+#[test]
+fn collects_all_problems() {
+    let input = Input {
+        article_locations: vec![location(0, 0), location(1, 0), location(1, 1)],
+        orders: vec![
+            Order {
+                order_id: 0,
+                article_ids: vec![0, 1, 7],
+            },
+            Order {
+                order_id: 0,
+                article_ids: vec![2],
+            },
+            Order {
+                order_id: 1,
+                article_ids: vec![],
+            },
+        ],
+        articles: vec![article(0, 10), article(0, 20), article(1, 0), article(2, 30)],
+    };
+
+    let errors = input.validate(&CostModel::default()).unwrap_err().errors;
+
+    let expected = vec![
+        InputError::UnknownArticle {
+            order_id: 0,
+            article_id: 7,
+        },
+        InputError::MissingLocation { article_id: 2 },
+        InputError::MissingLocation { article_id: 7 },
+        InputError::DuplicateArticle { article_id: 0 },
+        InputError::DuplicateOrder { order_id: 0 },
+        InputError::ManyLocations {
+            article_id: 1,
+            num_locations: 2,
+        },
+        InputError::EmptyOrder { order_id: 1 },
+        InputError::ZeroVolume { article_id: 1 },
+    ];
+    assert_eq!(errors.len(), expected.len());
+    for error in expected {
+        assert!(errors.contains(&error), "{} not reported", error);
+    }
+}
+
+#[test]
+fn model_rejects_invalid_input() {
+    let input = Input {
+        article_locations: vec![],
+        orders: vec![Order {
+            order_id: 0,
+            article_ids: vec![0],
+        }],
+        articles: vec![],
+    };
+
+    assert!(Model::from_input(&input, CostModel::default()).is_err());
+}
+
+#[test]
+fn ignores_volumes_of_unordered_articles() {
+    let input = Input {
+        article_locations: vec![location(0, 0), location(1, 0), location(2, 1)],
+        orders: vec![Order {
+            order_id: 0,
+            article_ids: vec![0],
+        }],
+        // neither article 1 nor article 2 fits into a batch, but no order contains them
+        articles: vec![article(0, 10), article(1, 0), article(2, 5000)],
+    };
+
+    assert_eq!(input.validate(&CostModel::default()), Ok(()));
+    assert!(Model::from_input(&input, CostModel::default()).is_ok());
+
+    let ordered = Input {
+        orders: vec![Order {
+            order_id: 0,
+            article_ids: vec![0, 1, 2],
+        }],
+        ..input
+    };
+    let errors = ordered.validate(&CostModel::default()).unwrap_err().errors;
+    assert_eq!(
+        errors,
+        vec![
+            InputError::ZeroVolume { article_id: 1 },
+            InputError::ExceedsMaxWeight {
+                article_id: 2,
+                volume: 5000,
+                max_weight: CostModel::default().max_weight_per_batch,
+            },
+        ]
+    );
+}
+
+#[test]
+fn rejects_input_without_orders() {
+    let input = Input {
+        article_locations: vec![location(0, 0)],
+        orders: vec![],
+        articles: vec![article(0, 10)],
+    };
+
+    assert_eq!(
+        input.validate(&CostModel::default()).unwrap_err().errors,
+        vec![InputError::NoOrders]
+    );
+    assert!(Model::from_input(&input, CostModel::default()).is_err());
+}
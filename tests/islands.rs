@@ -0,0 +1,80 @@
+use order_batching::ga::orders::{find_best_batches, BatchEncoding};
+use order_batching::ga::{GaParams, Infeasible, MIN_POPULATION_SIZE};
+use order_batching::{load_input, validate_output, CostModel, Model, Solver};
+
+fn model() -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, CostModel::default()).unwrap()
+}
+
+fn solver(model: &Model) -> Solver<'_> {
+    Solver::new(model)
+        .batch_population(20)
+        .batch_generations(25)
+        .waive_population(20)
+        .waive_generations(25)
+        .threads(3)
+        .migration_interval(5)
+        .seed(9)
+}
+
+#[test]
+fn islands_find_valid_solution() {
+    let model = model();
+    let solution = solver(&model).solve().unwrap();
+
+    assert_eq!(validate_output(&model, &solution.output).violations, vec![]);
+
+    let batch_stats = solution.batch_stats.unwrap();
+    assert_eq!(batch_stats.generations, 25);
+    assert_eq!(batch_stats.islands.len(), 3);
+    for island in batch_stats.islands {
+        assert_eq!(island.generations, 25);
+        // two migrants after each of the first four epochs
+        assert_eq!(island.migrants, 8);
+    }
+}
+
+#[test]
+fn islands_are_reproducible() {
+    let model = model();
+
+    let first = solver(&model).solve().unwrap();
+    let second = solver(&model).solve().unwrap();
+
+    assert_eq!(first.cost, second.cost);
+    assert_eq!(first.batch_stats, second.batch_stats);
+}
+
+#[test]
+fn single_thread_runs_single_population() {
+    let model = model();
+    let solution = solver(&model).threads(1).solve().unwrap();
+
+    assert!(solution.batch_stats.unwrap().islands.is_empty());
+}
+
+#[test]
+fn too_small_populations_fail_without_panicking() {
+    let model = model();
+
+    for threads in [1, 3] {
+        let error = solver(&model)
+            .threads(threads)
+            .waive_population(MIN_POPULATION_SIZE - 1)
+            .solve()
+            .unwrap_err();
+        assert!(error.to_string().contains("batches<->waives"), "{}", error);
+    }
+
+    let params = GaParams {
+        num_individuals: 1,
+        num_generations: 5,
+        seed: 1,
+        deadline: None,
+        stagnation: None,
+        islands: None,
+        infeasible: Infeasible::Reject,
+    };
+    assert!(find_best_batches(&model, params, BatchEncoding::Articles, &[]).is_err());
+}
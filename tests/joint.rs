@@ -0,0 +1,45 @@
+use order_batching::heuristics::greedy::{greedy_batches, greedy_waives};
+use order_batching::{load_input, validate_output, Algorithm, CostModel, Model, Solver};
+
+fn model() -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, CostModel::default()).unwrap()
+}
+
+#[test]
+fn joint_solution_is_valid() {
+    let model = model();
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Joint)
+        .batch_population(30)
+        .batch_generations(30)
+        .seed(11)
+        .solve()
+        .unwrap();
+
+    assert!(solution.joint_stats.is_some());
+    assert!(solution.batch_stats.is_none());
+    assert!(solution.waive_stats.is_none());
+    assert_eq!(validate_output(&model, &solution.output).violations, vec![]);
+}
+
+#[test]
+fn joint_search_is_not_worse_than_its_greedy_seed() {
+    let model = model();
+    let cost_model = model.cost_model();
+    let greedy_batched = greedy_batches(&model);
+    let greedy_waived = greedy_waives(&model, &greedy_batched);
+    let greedy_cost = greedy_batched.cost(cost_model).unwrap()
+        + greedy_waived.rest_cost(cost_model)
+        + greedy_waived.get_split_orders().len() * cost_model.penalty_per_split_order;
+
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Joint)
+        .batch_population(30)
+        .batch_generations(30)
+        .seed(11)
+        .solve()
+        .unwrap();
+
+    assert!(solution.cost.overall_cost() <= greedy_cost);
+}
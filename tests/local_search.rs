@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use order_batching::{load_input, validate_output, CostModel, Model, Solver};
+
+fn model() -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, CostModel::default()).unwrap()
+}
+
+#[test]
+fn local_search_improves_ga_batches() {
+    let model = model();
+    let solver = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .seed(3);
+
+    let without = solver.local_search(false).solve().unwrap();
+    let with = solver.local_search(true).solve().unwrap();
+
+    let stats = with.local_search_stats.unwrap();
+    assert!(without.local_search_stats.is_none());
+    assert_eq!(
+        stats.cost_before,
+        without.cost.tour_cost + without.cost.rest_cost_batches
+    );
+    assert_eq!(
+        stats.cost_after,
+        with.cost.tour_cost + with.cost.rest_cost_batches
+    );
+    assert!(stats.cost_after <= stats.cost_before);
+}
+
+#[test]
+fn local_search_keeps_solution_valid() {
+    let model = model();
+    let solution = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .solve()
+        .unwrap();
+
+    assert_eq!(validate_output(&model, &solution.output).violations, vec![]);
+}
+
+#[test]
+fn local_search_stops_at_the_time_limit() {
+    let model = model();
+    let solution = Solver::new(&model)
+        .time_limit(Duration::ZERO)
+        .solve()
+        .unwrap();
+
+    let stats = solution.local_search_stats.unwrap();
+    assert!(stats.timed_out);
+    assert_eq!(stats.cost_after, stats.cost_before);
+    assert!(stats.to_string().ends_with(", stopped by time limit"));
+}
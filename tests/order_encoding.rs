@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+
+use order_batching::ga::orders::article_groups;
+use order_batching::{
+    load_input, validate_output, Algorithm, BatchEncoding, CostModel, Model, Solver,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+/// Number of orders whose volume exceeds the max weight of a batch
+fn num_oversized_orders(model: &Model) -> usize {
+    let articles = model.get_ordered_articles();
+    let order_ids = articles
+        .iter()
+        .map(|article| article.order_id)
+        .collect::<BTreeSet<_>>();
+    order_ids
+        .into_iter()
+        .filter(|order_id| {
+            let volume = articles
+                .iter()
+                .filter(|article| article.order_id == *order_id)
+                .map(|article| article.volume)
+                .sum::<usize>();
+            volume > model.cost_model().max_weight_per_batch
+        })
+        .count()
+}
+
+#[test]
+fn groups_hold_single_orders_within_max_weight() {
+    let model = model(CostModel::default());
+    let articles = model.get_ordered_articles();
+    let groups = article_groups(&model, BatchEncoding::Orders);
+
+    let mut indices = groups.iter().flatten().copied().collect::<Vec<_>>();
+    indices.sort();
+    assert_eq!(indices, (0..articles.len()).collect::<Vec<_>>());
+    assert!(groups.len() < articles.len());
+    for group in groups {
+        let order_ids = group
+            .iter()
+            .map(|idx| articles[*idx].order_id)
+            .collect::<BTreeSet<_>>();
+        let volume = group.iter().map(|idx| articles[*idx].volume).sum::<usize>();
+        assert_eq!(order_ids.len(), 1);
+        assert!(group.len() == 1 || volume <= model.cost_model().max_weight_per_batch);
+    }
+}
+
+#[test]
+fn order_encoding_splits_only_oversized_orders() {
+    let model = model(CostModel::default());
+    let num_oversized_orders = num_oversized_orders(&model);
+    assert!(num_oversized_orders > 0);
+
+    for algorithm in [Algorithm::Greedy, Algorithm::Ga] {
+        let solution = Solver::new(&model)
+            .algorithm(algorithm)
+            .batch_encoding(BatchEncoding::Orders)
+            .batch_population(20)
+            .batch_generations(10)
+            .waive_population(20)
+            .waive_generations(10)
+            .seed(5)
+            .solve()
+            .unwrap();
+
+        let report = validate_output(&model, &solution.output);
+        assert_eq!(report.violations, vec![]);
+        assert!(solution.cost.num_split_orders <= num_oversized_orders);
+        assert_eq!(report.cost.num_split_orders, solution.cost.num_split_orders);
+    }
+}
+
+#[test]
+fn joint_search_keeps_order_genes_together() {
+    // most orders fit into a batch
+    let model = model(CostModel {
+        max_weight_per_batch: 3000,
+        ..CostModel::default()
+    });
+
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Joint)
+        .batch_encoding(BatchEncoding::Orders)
+        .batch_population(50)
+        .batch_generations(100)
+        .seed(5)
+        .solve()
+        .unwrap();
+
+    assert_eq!(validate_output(&model, &solution.output).violations, vec![]);
+    assert!(solution.cost.num_split_orders <= num_oversized_orders(&model));
+}
+
+#[test]
+fn article_encoding_reports_split_orders() {
+    let model = model(CostModel::default());
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Greedy)
+        .solve()
+        .unwrap();
+
+    let report = validate_output(&model, &solution.output);
+    assert_eq!(report.cost.num_split_orders, solution.cost.num_split_orders);
+    assert!(solution.cost.num_split_orders > 0);
+}
@@ -0,0 +1,122 @@
+use order_batching::model::{ArticleLocation, OrderedArticle};
+use order_batching::routing::route;
+use order_batching::{load_input, Algorithm, CostModel, Model, RoutingPolicy, Solver, TourModel};
+
+fn article(id: u16, warehouse: u16, aisle: u16, position: u16) -> OrderedArticle {
+    OrderedArticle {
+        order_id: 0,
+        id,
+        volume: 1,
+        location: ArticleLocation {
+            warehouse,
+            aisle,
+            position,
+        },
+    }
+}
+
+fn cost_model() -> CostModel {
+    CostModel {
+        aisle_length: 10,
+        cost_per_position: 1,
+        cost_per_aisle_step: 3,
+        ..CostModel::default()
+    }
+}
+
+fn article_ids(articles: &[&OrderedArticle]) -> Vec<u16> {
+    articles.iter().map(|article| article.id).collect()
+}
+
+#[test]
+fn return_route_matches_distance_tour_model() {
+    let articles = [
+        article(0, 0, 2, 7),
+        article(1, 0, 0, 3),
+        article(2, 1, 1, 0),
+        article(3, 0, 2, 1),
+    ];
+    let articles = articles.iter().collect::<Vec<_>>();
+    let cost_model = CostModel {
+        tour_model: TourModel::Distance,
+        ..cost_model()
+    };
+
+    let route = route(&articles, RoutingPolicy::Return, &cost_model);
+
+    assert_eq!(article_ids(&route.articles), vec![1, 3, 0, 2]);
+    assert_eq!(
+        route.length,
+        cost_model.picking_cost(articles.iter().map(|article| &article.location))
+            - 2 * cost_model.cost_per_warehouse
+    );
+}
+
+#[test]
+fn s_shape_alternates_directions() {
+    let articles = [
+        article(0, 0, 0, 5),
+        article(1, 0, 0, 1),
+        article(2, 0, 1, 2),
+        article(3, 0, 1, 8),
+        article(4, 0, 3, 4),
+    ];
+    let articles = articles.iter().collect::<Vec<_>>();
+
+    let route = route(&articles, RoutingPolicy::SShape, &cost_model());
+
+    assert_eq!(article_ids(&route.articles), vec![1, 0, 3, 2, 4]);
+    // two traversed aisles of length 11, returning from position 4 in aisle 3
+    assert_eq!(route.length, 2 * 11 + 2 * 5 + 2 * 3 * 3);
+}
+
+#[test]
+fn largest_gap_leaves_out_the_largest_gap() {
+    let articles = [
+        article(0, 0, 0, 5),
+        article(1, 0, 1, 0),
+        article(2, 0, 1, 9),
+        article(3, 0, 2, 3),
+    ];
+    let articles = articles.iter().collect::<Vec<_>>();
+
+    let route = route(&articles, RoutingPolicy::LargestGap, &cost_model());
+
+    assert_eq!(article_ids(&route.articles), vec![0, 2, 3, 1]);
+    // first and last aisle traversed, aisle 1 without the gap between positions 0 and 9
+    assert_eq!(route.length, 2 * 11 + 2 * (11 - 9) + 2 * 2 * 3);
+}
+
+#[test]
+fn optimal_within_aisle_is_not_longer_than_other_policies() {
+    let articles = (0..24)
+        .map(|id| article(id, id % 2, (id * 7) % 5, (id * 13) % 10))
+        .collect::<Vec<_>>();
+    let articles = articles.iter().collect::<Vec<_>>();
+    let cost_model = cost_model();
+
+    let optimal = route(&articles, RoutingPolicy::OptimalWithinAisle, &cost_model);
+
+    assert_eq!(optimal.articles.len(), articles.len());
+    for policy in [RoutingPolicy::Return, RoutingPolicy::SShape] {
+        assert!(optimal.length <= route(&articles, policy, &cost_model).length);
+    }
+}
+
+#[test]
+fn output_numbers_items_in_route_order() {
+    let input = load_input("input1.txt").unwrap();
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Greedy)
+        .routing_policy(RoutingPolicy::SShape)
+        .solve()
+        .unwrap();
+
+    for batch in solution.output.batches {
+        let sequence = batch.items.iter().map(|item| item.sequence).collect::<Vec<_>>();
+        assert_eq!(sequence, (1..=batch.items.len()).collect::<Vec<_>>());
+        assert!(batch.route_length > 0);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use order_batching::ga::{Stagnation, StopReason};
+use order_batching::{load_input, CostModel, Input, Model, Solution, Solver};
+
+fn solve(input: &Input) -> Solution {
+    let model = Model::from_input(input, CostModel::default()).unwrap();
+    Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .solve()
+        .expect("input1.txt is solvable")
+}
+
+/// Counts how often each (order id, article id) pair occurs
+fn count_items<I: IntoIterator<Item = (u16, u16)>>(items: I) -> BTreeMap<(u16, u16), usize> {
+    let mut counts = BTreeMap::new();
+    for item in items {
+        *counts.entry(item).or_insert(0) += 1;
+    }
+    counts
+}
+
+#[test]
+fn every_ordered_article_is_picked_exactly_once() {
+    let input = load_input("input1.txt").unwrap();
+    let solution = solve(&input);
+
+    let ordered = count_items(input.orders.iter().flat_map(|order| {
+        order
+            .article_ids
+            .iter()
+            .map(move |article_id| (order.order_id, *article_id))
+    }));
+    let picked = count_items(solution.output.batches.iter().flat_map(|batch| {
+        batch
+            .items
+            .iter()
+            .map(|item| (item.order_id, item.article_id))
+    }));
+
+    assert_eq!(ordered, picked);
+}
+
+#[test]
+fn batches_and_waves_respect_limits() {
+    let input = load_input("input1.txt").unwrap();
+    let solution = solve(&input);
+    let cost_model = CostModel::default();
+
+    for batch in &solution.output.batches {
+        assert!(batch.batch_volume <= cost_model.max_weight_per_batch);
+    }
+    for wave in &solution.output.waves {
+        assert!(wave.wave_size <= cost_model.max_articles_per_waive);
+    }
+
+    let num_batches_in_waves = solution
+        .output
+        .waves
+        .iter()
+        .map(|wave| wave.batch_ids.len())
+        .sum::<usize>();
+    assert_eq!(num_batches_in_waves, solution.output.batches.len());
+}
+
+#[test]
+fn cost_breakdown_matches_output() {
+    let input = load_input("input1.txt").unwrap();
+    let solution = solve(&input);
+    let cost = solution.cost;
+
+    assert_eq!(cost.num_batches, solution.output.batches.len());
+    assert_eq!(cost.num_waives, solution.output.waves.len());
+    assert_eq!(
+        cost.overall_cost(),
+        cost.tour_cost + cost.rest_cost_batches + cost.rest_cost_waives
+    );
+}
+
+#[test]
+fn runs_with_same_seed_are_identical() {
+    let input = load_input("input1.txt").unwrap();
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+    let solver = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .seed(42);
+
+    let first = solver.solve().unwrap();
+    let second = solver.solve().unwrap();
+
+    assert_eq!(first.output.seed, Some(42));
+    assert_eq!(first.cost, second.cost);
+    assert_eq!(
+        serde_json::to_string(&first.output).unwrap(),
+        serde_json::to_string(&second.output).unwrap()
+    );
+}
+
+#[test]
+fn time_limit_stops_search() {
+    let input = load_input("input1.txt").unwrap();
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+
+    let started_at = Instant::now();
+    let solution = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(usize::MAX)
+        .waive_population(30)
+        .waive_generations(usize::MAX)
+        .time_limit(Duration::from_millis(500))
+        .solve()
+        .unwrap();
+
+    assert!(started_at.elapsed() < Duration::from_secs(5));
+    assert!(!solution.output.batches.is_empty());
+}
+
+#[test]
+fn stagnation_stops_search_early() {
+    let input = load_input("input1.txt").unwrap();
+    let model = Model::from_input(&input, CostModel::default()).unwrap();
+    let stagnation = Stagnation {
+        generations: 5,
+        epsilon: 0.0,
+    };
+
+    let solution = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(100_000)
+        .waive_population(30)
+        .waive_generations(100_000)
+        .batch_stagnation(stagnation)
+        .waive_stagnation(stagnation)
+        .seed(1)
+        .solve()
+        .unwrap();
+
+    for stats in [solution.batch_stats, solution.waive_stats] {
+        let stats = stats.unwrap();
+        assert_ne!(stats.stop_reason, StopReason::GenerationLimit);
+        assert!(stats.generations < 100_000);
+    }
+}
@@ -0,0 +1,111 @@
+use order_batching::batching::Evaluator;
+use order_batching::ga::orders::BatchedArticles;
+use order_batching::heuristics::greedy::greedy_encoded_batches;
+use order_batching::heuristics::local_search::improve_batches;
+use order_batching::{
+    load_input, validate_output, Algorithm, BatchEncoding, CostModel, Model, Solver,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+fn solver(model: &Model, algorithm: Algorithm) -> Solver<'_> {
+    Solver::new(model)
+        .algorithm(algorithm)
+        .batch_population(20)
+        .batch_generations(20)
+        .waive_population(20)
+        .waive_generations(10)
+        .seed(9)
+}
+
+#[test]
+fn split_penalties_are_part_of_the_overall_cost() {
+    let model = model(CostModel {
+        max_articles_per_waive: 20,
+        penalty_per_batch_split: 7,
+        penalty_per_waive_split: 3,
+        ..CostModel::default()
+    });
+
+    for algorithm in [Algorithm::Greedy, Algorithm::Ga, Algorithm::Joint] {
+        let solution = solver(&model, algorithm).solve().unwrap();
+        let cost = solution.cost;
+        assert!(cost.num_split_orders > 0);
+        assert_eq!(
+            cost.split_cost,
+            cost.num_split_orders * 7 + cost.num_waive_split_orders * 3
+        );
+        assert_eq!(
+            cost.overall_cost(),
+            cost.tour_cost + cost.rest_cost_batches + cost.rest_cost_waives + cost.split_cost
+        );
+
+        let report = validate_output(&model, &solution.output);
+        assert_eq!(report.violations, vec![]);
+        assert_eq!(report.cost, cost);
+    }
+}
+
+#[test]
+fn output_lists_split_orders() {
+    let model = model(CostModel {
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+    let solution = solver(&model, Algorithm::Greedy).solve().unwrap();
+
+    let split_orders = solution.output.split_orders.unwrap();
+    assert_eq!(split_orders.batches.len(), solution.cost.num_split_orders);
+    assert_eq!(
+        split_orders.waves.len(),
+        solution.cost.num_waive_split_orders
+    );
+    for order_id in split_orders.waves {
+        let num_waves = solution
+            .output
+            .waves
+            .iter()
+            .filter(|wave| wave.order_ids.contains(&order_id))
+            .count();
+        assert!(num_waves > 1);
+    }
+}
+
+#[test]
+fn local_search_rejoins_split_orders() {
+    let model = model(CostModel {
+        penalty_per_batch_split: 100,
+        ..CostModel::default()
+    });
+    let batched_articles = greedy_encoded_batches(&model, BatchEncoding::Orders);
+    let num_split_orders = batched_articles.get_split_orders().len();
+
+    // splits a single order by moving one of its articles into another batch where it fits
+    let evaluator = Evaluator::new(&model);
+    let mut batching = evaluator.batching(batched_articles.batch_mapping().clone());
+    // the articles of every order are next to each other
+    let (idx, to) = (1..evaluator.num_articles())
+        .filter(|idx| evaluator.article(*idx).order_id == evaluator.article(idx - 1).order_id)
+        .filter(|idx| {
+            !batched_articles
+                .get_split_orders()
+                .contains(&evaluator.article(*idx).order_id)
+        })
+        .find_map(|idx| {
+            batching
+                .non_empty_batch_ids()
+                .into_iter()
+                .find(|to| *to != batching.batch_of(idx) && batching.fits(idx, *to))
+                .map(|to| (idx, to))
+        })
+        .unwrap();
+    batching.move_article(idx, to);
+    assert_eq!(batching.num_split_orders(), num_split_orders + 1);
+
+    let split = BatchedArticles::from_batch_mapping(batching.into_batch_mapping(), &model);
+    let (improved, _) = improve_batches(&model, &split, BatchEncoding::Articles, None);
+    assert!(improved.get_split_orders().len() <= num_split_orders);
+}
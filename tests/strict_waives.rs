@@ -0,0 +1,97 @@
+use std::collections::BTreeSet;
+
+use order_batching::ga::batches::WaivedBatches;
+use order_batching::heuristics::greedy::greedy_batches;
+use order_batching::heuristics::repair::{check_orders_fit_waives, keep_orders_in_waives};
+use order_batching::{
+    load_input, validate_output, Algorithm, BatchEncoding, CostModel, Model, Solver,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+#[test]
+fn strict_waives_keep_orders_together() {
+    // every order fits into a batch, but not all orders fit into a single waive
+    let model = model(CostModel {
+        max_weight_per_batch: 3000,
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+
+    for algorithm in [Algorithm::Greedy, Algorithm::Ga, Algorithm::Joint] {
+        let solution = Solver::new(&model)
+            .algorithm(algorithm)
+            .batch_encoding(BatchEncoding::Orders)
+            .strict_waives(true)
+            .batch_population(20)
+            .batch_generations(10)
+            .waive_population(20)
+            .waive_generations(10)
+            .seed(3)
+            .solve()
+            .unwrap();
+
+        let report = validate_output(&model, &solution.output);
+        assert_eq!(report.violations, vec![]);
+        assert!(solution.output.waves.len() > 1);
+
+        let mut seen = BTreeSet::new();
+        for wave in solution.output.waves.iter() {
+            for order_id in wave.order_ids.iter() {
+                assert!(seen.insert(*order_id), "order {} split", order_id);
+            }
+        }
+    }
+}
+
+#[test]
+fn repair_merges_waives_that_share_orders() {
+    let model = model(CostModel::default());
+    let batched_articles = greedy_batches(&model);
+    let num_batches = batched_articles.to_batches().len();
+    // every batch in a waive of its own
+    let waive_mapping = (0..num_batches).collect::<Vec<_>>();
+    assert!(
+        WaivedBatches::from_waive_mapping(waive_mapping.clone(), &batched_articles)
+            .has_split_orders()
+    );
+
+    let repaired = keep_orders_in_waives(&model, &batched_articles, &waive_mapping).unwrap();
+    let waived_batches = WaivedBatches::from_waive_mapping(repaired, &batched_articles);
+    assert!(!waived_batches.has_split_orders());
+    for waive in waived_batches.to_waives() {
+        assert!(waive.num_articles() <= model.cost_model().max_articles_per_waive);
+    }
+}
+
+#[test]
+fn strict_waives_fail_if_orders_exceed_a_waive() {
+    // the largest order of input1 has 9 articles
+    let small_waives = model(CostModel {
+        max_articles_per_waive: 8,
+        ..CostModel::default()
+    });
+    assert!(check_orders_fit_waives(&small_waives).is_err());
+
+    let err = Solver::new(&small_waives)
+        .algorithm(Algorithm::Greedy)
+        .strict_waives(true)
+        .solve()
+        .unwrap_err();
+    assert!(err.to_string().contains("Cannot keep orders"));
+
+    // linked by the batches, all orders of input1 need a single waive
+    let linked = model(CostModel {
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+    let err = Solver::new(&linked)
+        .algorithm(Algorithm::Greedy)
+        .strict_waives(true)
+        .solve()
+        .unwrap_err();
+    assert!(err.to_string().contains("Cannot keep orders"));
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeSet;
+
+use order_batching::heuristics::greedy::{greedy_batches, greedy_waives};
+use order_batching::heuristics::tabu::{find_best_waives_tabu, Aspiration, TabuParams};
+use order_batching::{
+    load_input, validate_output, Algorithm, BatchEncoding, CostModel, Model, Solver, WaiveSearch,
+};
+
+fn model(cost_model: CostModel) -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, cost_model).unwrap()
+}
+
+#[test]
+fn tabu_search_improves_a_bad_seed() {
+    let model = model(CostModel {
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+    let cost_model = model.cost_model();
+    let batched_articles = greedy_batches(&model);
+    let greedy = greedy_waives(&model, &batched_articles);
+    // every batch in a waive of its own
+    let seeds = [(0..batched_articles.to_batches().len()).collect::<Vec<_>>()];
+
+    for aspiration in [Aspiration::BestCost, Aspiration::None] {
+        let params = TabuParams {
+            aspiration,
+            seed: 1,
+            ..TabuParams::default()
+        };
+        let (waived_batches, stats) =
+            find_best_waives_tabu(&model, &batched_articles, params, false, &seeds).unwrap();
+
+        assert!(stats.cost_after < stats.cost_before);
+        assert!(stats.improvements > 0);
+        for waive in waived_batches.to_waives() {
+            assert!(waive.num_articles() <= 20);
+        }
+        assert!(
+            waived_batches.to_waives().len() <= greedy.to_waives().len(),
+            "{} waives, greedy has {}",
+            waived_batches.to_waives().len(),
+            greedy.to_waives().len()
+        );
+        assert_eq!(
+            stats.cost_after,
+            waived_batches.rest_cost(cost_model)
+                + waived_batches.get_split_orders().len()
+                    * (cost_model.penalty_per_split_order + cost_model.penalty_per_waive_split)
+        );
+    }
+}
+
+#[test]
+fn tabu_waive_search_replaces_the_genetic_one() {
+    // every order fits into a batch, but not all orders fit into a single waive
+    let model = model(CostModel {
+        max_weight_per_batch: 3000,
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+
+    let solution = Solver::new(&model)
+        .algorithm(Algorithm::Ga)
+        .waive_search(WaiveSearch::Tabu)
+        .batch_encoding(BatchEncoding::Orders)
+        .strict_waives(true)
+        .batch_population(20)
+        .batch_generations(10)
+        .seed(3)
+        .solve()
+        .unwrap();
+
+    let report = validate_output(&model, &solution.output);
+    assert_eq!(report.violations, vec![]);
+    assert!(solution.tabu_stats.is_some());
+    assert!(solution.waive_stats.is_none());
+
+    let mut seen = BTreeSet::new();
+    for wave in solution.output.waves.iter() {
+        for order_id in wave.order_ids.iter() {
+            assert!(seen.insert(*order_id), "order {} split", order_id);
+        }
+    }
+}
+
+#[test]
+fn tabu_search_is_reproducible_and_stagnates() {
+    let model = model(CostModel {
+        max_articles_per_waive: 20,
+        ..CostModel::default()
+    });
+    let batched_articles = greedy_batches(&model);
+    let params = TabuParams {
+        iterations: 1000,
+        tenure: 3,
+        tenure_spread: 2,
+        stagnation: Some(10),
+        seed: 4,
+        ..TabuParams::default()
+    };
+
+    let (waived_batches, stats) =
+        find_best_waives_tabu(&model, &batched_articles, params, false, &[]).unwrap();
+    let (again, again_stats) =
+        find_best_waives_tabu(&model, &batched_articles, params, false, &[]).unwrap();
+
+    assert_eq!(waived_batches.waive_mapping(), again.waive_mapping());
+    assert_eq!(stats, again_stats);
+    assert!(stats.iterations < 1000);
+    assert!(!stats.timed_out);
+}
@@ -0,0 +1,81 @@
+use order_batching::model::ArticleLocation;
+use order_batching::{load_input, validate_output, CostModel, Model, Solver, TourModel};
+
+fn location(warehouse: u16, aisle: u16, position: u16) -> ArticleLocation {
+    ArticleLocation {
+        warehouse,
+        aisle,
+        position,
+    }
+}
+
+#[test]
+fn counting_ignores_positions() {
+    let cost_model = CostModel::default();
+    let near = [location(0, 1, 0), location(0, 1, 1)];
+    let far = [location(0, 1, 0), location(0, 1, 19)];
+
+    assert_eq!(
+        cost_model.picking_cost(&near),
+        cost_model.cost_per_warehouse + cost_model.cost_per_aisle
+    );
+    assert_eq!(cost_model.picking_cost(&near), cost_model.picking_cost(&far));
+}
+
+#[test]
+fn distance_sums_up_cross_and_in_aisle_travel() {
+    let cost_model = CostModel {
+        tour_model: TourModel::Distance,
+        cost_per_warehouse: 10,
+        cost_per_position: 1,
+        cost_per_aisle_step: 3,
+        ..CostModel::default()
+    };
+    let locations = [
+        location(0, 1, 4),
+        location(0, 1, 9),
+        location(0, 3, 0),
+        location(1, 0, 2),
+    ];
+
+    // warehouse 0: 2 * 3 aisle steps, aisle 1 up to position 9, aisle 3 up to position 0
+    let warehouse_0 = 10 + 2 * 3 * 3 + 2 * 10 + 2;
+    // warehouse 1: no aisle steps, aisle 0 up to position 2
+    let warehouse_1 = 10 + 2 * 3;
+    assert_eq!(cost_model.picking_cost(&locations), warehouse_0 + warehouse_1);
+}
+
+#[test]
+fn distance_tour_model_solves_input1() {
+    let input = load_input("input1.txt").unwrap();
+    let cost_model = CostModel {
+        tour_model: TourModel::Distance,
+        ..CostModel::default()
+    };
+    let model = Model::from_input(&input, cost_model).unwrap();
+
+    let solution = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .seed(2)
+        .solve()
+        .unwrap();
+    let report = validate_output(&model, &solution.output);
+
+    assert_eq!(report.violations, vec![]);
+    assert_eq!(report.cost.tour_cost, solution.cost.tour_cost);
+}
+
+#[test]
+fn distance_tour_model_rejects_positions_outside_of_aisles() {
+    let input = load_input("input1.txt").unwrap();
+    let cost_model = CostModel {
+        tour_model: TourModel::Distance,
+        aisle_length: 10,
+        ..CostModel::default()
+    };
+
+    assert!(Model::from_input(&input, cost_model).is_err());
+}
@@ -0,0 +1,85 @@
+use order_batching::{load_input, validate_output, CostModel, Model, Solver, Violation};
+
+fn model() -> Model {
+    let input = load_input("input1.txt").unwrap();
+    Model::from_input(&input, CostModel::default()).unwrap()
+}
+
+#[test]
+fn solver_output_is_valid_and_scored_equally() {
+    let model = model();
+    let solution = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .solve()
+        .unwrap();
+
+    let report = validate_output(&model, &solution.output);
+
+    assert_eq!(report.violations, vec![]);
+    assert_eq!(report.cost, solution.cost);
+}
+
+#[test]
+fn detects_tampered_output() {
+    let model = model();
+    let mut output = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .solve()
+        .unwrap()
+        .output;
+
+    let removed_item = output.batches[0].items.pop().unwrap();
+    output.batches[0].items.push(removed_item.clone());
+    output.batches[0].items.push(removed_item);
+    output.batches[1].batch_volume += 1;
+    let batch_id = output.batches[0].batch_id;
+    output.waves[0].batch_ids.push(batch_id);
+
+    let report = validate_output(&model, &output);
+
+    assert!(!report.is_valid());
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| matches!(v, Violation::UnorderedArticle { .. })));
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| matches!(v, Violation::WrongBatchVolume { .. })));
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| matches!(v, Violation::WrongWaveSize { .. })));
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| matches!(v, Violation::BatchInManyWaves { .. })));
+}
+
+#[test]
+fn detects_missing_articles() {
+    let model = model();
+    let mut output = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .solve()
+        .unwrap()
+        .output;
+
+    let removed_item = output.batches[0].items.pop().unwrap();
+
+    let report = validate_output(&model, &output);
+
+    assert!(report.violations.contains(&Violation::MissingArticle {
+        order_id: removed_item.order_id,
+        article_id: removed_item.article_id,
+    }));
+}
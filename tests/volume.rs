@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use order_batching::input::{Article, ArticleLocation, Order};
+use order_batching::{load_input, CostModel, Input, InputError, Model, Solver};
+
+/// Builds an input with a single order, all articles in one aisle
+fn input_with_volumes(volumes: &[u16]) -> Input {
+    let article_ids = (0..volumes.len() as u16).collect::<Vec<_>>();
+
+    Input {
+        article_locations: article_ids
+            .iter()
+            .map(|id| ArticleLocation {
+                warehouse: 0,
+                aisle: 0,
+                position: *id,
+                article_id: *id,
+            })
+            .collect(),
+        orders: vec![Order {
+            order_id: 0,
+            article_ids: article_ids.clone(),
+        }],
+        articles: article_ids
+            .iter()
+            .zip(volumes)
+            .map(|(id, volume)| Article {
+                article_id: *id,
+                volume: *volume,
+            })
+            .collect(),
+    }
+}
+
+/// Asserts that all batches stay below capacity and report their untruncated volume
+fn assert_batch_volumes(input: &Input) {
+    let model = Model::from_input(input, CostModel::default()).unwrap();
+    let solution = Solver::new(&model)
+        .batch_population(30)
+        .batch_generations(30)
+        .waive_population(30)
+        .waive_generations(30)
+        .solve()
+        .unwrap();
+
+    let volumes = input
+        .articles
+        .iter()
+        .map(|article| (article.article_id, article.volume as usize))
+        .collect::<BTreeMap<_, _>>();
+
+    for batch in &solution.output.batches {
+        let volume = batch
+            .items
+            .iter()
+            .map(|item| volumes[&item.article_id])
+            .sum::<usize>();
+
+        assert_eq!(batch.batch_volume, volume);
+        assert!(batch.batch_volume <= CostModel::default().max_weight_per_batch);
+    }
+}
+
+#[test]
+fn large_volumes_are_not_truncated() {
+    assert_batch_volumes(&input_with_volumes(&[360, 900, 700, 480]));
+}
+
+#[test]
+fn batch_volumes_of_input1_are_not_truncated() {
+    assert_batch_volumes(&load_input("input1.txt").unwrap());
+}
+
+#[test]
+fn articles_exceeding_batch_capacity_are_rejected() {
+    let input = input_with_volumes(&[300, 1200]);
+
+    let err = Model::from_input(&input, CostModel::default()).unwrap_err();
+
+    assert_eq!(
+        err.errors,
+        vec![InputError::ExceedsMaxWeight {
+            article_id: 1,
+            volume: 1200,
+            max_weight: 1000,
+        }]
+    );
+}